@@ -0,0 +1,105 @@
+use clap::{ArgAction, Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::PathBuf;
+
+use crate::config::Backend;
+use crate::network::SortMode;
+use crate::output::Output;
+
+// Keeps the layout and headings of the old hand-written help: name and
+// version first, then USAGE and OPTIONS
+const HELP_TEMPLATE: &str = "\
+{name} {version}
+{about}
+
+USAGE:
+    {usage}
+
+COMMANDS:
+{subcommands}
+
+OPTIONS:
+{options}{after-help}";
+
+const AFTER_HELP: &str = "\
+EXIT STATUS:
+    0 success, 1 operation failed, 2 usage error, 3 network or profile not found";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = env!("CARGO_PKG_NAME"),
+    version = concat!("v", env!("CARGO_PKG_VERSION")),
+    about = "A network management tool using ncurses",
+    help_template = HELP_TEMPLATE,
+    after_help = AFTER_HELP,
+    disable_version_flag = true
+)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Print version information
+    #[arg(short = 'v', long, action = ArgAction::Version)]
+    version: Option<bool>,
+
+    /// Join the network in a WIFI:... QR payload
+    #[arg(long, value_name = "PAYLOAD", conflicts_with = "join_qr_file")]
+    pub join_qr: Option<String>,
+
+    /// Join the network in a decoded QR text file
+    #[arg(long, value_name = "FILE")]
+    pub join_qr_file: Option<PathBuf>,
+
+    /// Wi-Fi device to scan and connect with
+    #[arg(short = 'i', long, value_name = "IFACE", global = true)]
+    pub interface: Option<String>,
+
+    /// Network backend to use
+    #[arg(long, value_enum, global = true)]
+    pub backend: Option<Backend>,
+
+    /// Read settings from FILE instead of $XDG_CONFIG_HOME/nmcurse/config.toml
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Colour theme: dark, light, monochrome or one from the config file
+    #[arg(long, value_name = "THEME", global = true)]
+    pub theme: Option<String>,
+
+    /// Draw without colours, same as --theme monochrome
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Order of the network list
+    #[arg(long, value_enum, global = true)]
+    pub sort: Option<SortMode>,
+
+    /// Rescan the Wi-Fi list every SECS seconds, 0 to turn off
+    #[arg(long, value_name = "SECS", global = true)]
+    pub refresh_interval: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// List visible networks
+    List(Output),
+    /// Connect to a network
+    Connect {
+        #[arg(required_unless_present = "favorite")]
+        ssid: Option<String>,
+        /// Connect to the favorite network with the strongest signal
+        #[arg(long, conflicts_with = "ssid")]
+        favorite: bool,
+        /// Read the password from the first line of standard input
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Disconnect from SSID, or from all Wi-Fi networks
+    Disconnect { ssid: Option<String> },
+    /// Delete every saved profile for SSID
+    Forget { ssid: String },
+    /// Show radio and Wi-Fi device state
+    Status(Output),
+    /// Print a completion script for SHELL
+    Completions { shell: Shell },
+}
@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use zeroize::Zeroizing;
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+use crate::args::{Args, Command};
+use crate::config::Settings;
+use crate::hotspot::HOTSPOT_PROFILE;
+use crate::network::SortMode;
+use crate::nmcli::{self, ProfileStatus};
+use crate::output::{Output, OutputError, DEVICE_FIELDS, NETWORK_FIELDS};
+use crate::radio::RadioState;
+use crate::state::{self, Event, Favorites};
+
+const NAME: &str = env!("CARGO_PKG_NAME");
+
+pub const EXIT_OK: i32 = 0;
+/// nmcli or NetworkManager reported an error.
+pub const EXIT_FAILURE: i32 = 1;
+/// The command line could not be parsed.
+pub const EXIT_USAGE: i32 = 2;
+/// The network or profile named on the command line does not exist.
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// Runs a subcommand and returns the process exit code.
+pub fn run(command: &Command, settings: &Settings) -> i32 {
+    let interface = settings.interface.as_deref();
+    match command {
+        Command::List(output) => list(output, interface, settings.sort),
+        Command::Connect {
+            ssid,
+            favorite: _,
+            password_stdin,
+        } => connect(ssid.as_deref(), *password_stdin, interface),
+        Command::Disconnect { ssid: None } => disconnect_all(interface),
+        Command::Disconnect { ssid: Some(ssid) } => disconnect(ssid),
+        Command::Forget { ssid } => forget(ssid),
+        Command::Status(output) => status(output, interface),
+        Command::Completions { shell } => completions(*shell),
+    }
+}
+
+// Generated into memory first: clap_complete panics when it cannot write
+fn completions(shell: Shell) -> i32 {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Args::command(), NAME, &mut script);
+    let mut out = io::stdout().lock();
+    match out.write_all(&script).and_then(|()| out.flush()) {
+        Ok(()) => EXIT_OK,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => EXIT_OK,
+        Err(e) => fail(format!("cannot write completions: {e}"), EXIT_FAILURE),
+    }
+}
+
+fn usage(message: &str) -> i32 {
+    eprintln!("{NAME}: {message}");
+    eprintln!("Run '{NAME} --help' for usage");
+    EXIT_USAGE
+}
+
+fn fail(message: impl std::fmt::Display, code: i32) -> i32 {
+    eprintln!("{NAME}: {message}");
+    code
+}
+
+fn list(output: &Output, interface: Option<&str>, sort: SortMode) -> i32 {
+    if let Err(e) = output.validate(&NETWORK_FIELDS) {
+        return usage(&e);
+    }
+    match nmcli::scan(interface) {
+        Ok(mut networks) => {
+            let saved = if sort.needs_profiles() {
+                nmcli::saved_ssids()
+            } else {
+                HashSet::new()
+            };
+            sort.apply(&mut networks, &saved);
+            finish(output.print_networks(&networks))
+        }
+        Err(e) => fail(e, EXIT_FAILURE),
+    }
+}
+
+fn finish(printed: Result<(), OutputError>) -> i32 {
+    match printed {
+        Ok(()) => EXIT_OK,
+        Err(OutputError::Fields(e)) => usage(&e),
+        // The reader went away, e.g. `| head`; nothing is left to do
+        Err(OutputError::Write(e)) if e.kind() == io::ErrorKind::BrokenPipe => EXIT_OK,
+        Err(OutputError::Write(e)) => fail(format!("cannot write output: {e}"), EXIT_FAILURE),
+    }
+}
+
+// Without an SSID, picks the favorite in range with the strongest signal
+fn connect(ssid: Option<&str>, password_stdin: bool, interface: Option<&str>) -> i32 {
+    let password = if password_stdin {
+        let mut line = Zeroizing::new(String::new());
+        if let Err(e) = io::stdin().lock().read_line(&mut line) {
+            return fail(format!("could not read password: {e}"), EXIT_FAILURE);
+        }
+        Zeroizing::new(line.trim_end_matches(['\r', '\n']).to_string())
+    } else {
+        Zeroizing::new(String::new())
+    };
+
+    let networks = match nmcli::scan(interface) {
+        Ok(networks) => networks,
+        Err(e) => return fail(e, EXIT_FAILURE),
+    };
+    let network = match ssid {
+        Some(ssid) => networks.iter().find(|n| n.ssid == ssid),
+        None => {
+            let favorites = match Favorites::load() {
+                Ok(favorites) => favorites,
+                Err(e) => return fail(e, EXIT_FAILURE),
+            };
+            networks
+                .iter()
+                .filter(|n| favorites.contains(&n.ssid))
+                .max_by_key(|n| n.signal)
+        }
+    };
+    let Some(network) = network else {
+        return match ssid {
+            Some(ssid) => fail(format!("network '{ssid}' not found"), EXIT_NOT_FOUND),
+            None => fail("no favorite network in range", EXIT_NOT_FOUND),
+        };
+    };
+    let ssid = network.ssid.as_str();
+
+    let status = nmcli::profile_status(ssid);
+    let needs_password = !network.security.is_empty()
+        && !matches!(status, ProfileStatus::SavedSecret(_))
+        && password.is_empty();
+    if needs_password {
+        return fail(
+            format!("'{ssid}' needs a password, pass it with --password-stdin"),
+            EXIT_USAGE,
+        );
+    }
+
+    let result = nmcli::activate(&status, ssid, &password, false, interface);
+    state::record(Event::Connect, ssid, &network.bssid, &result);
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => fail(e, EXIT_FAILURE),
+    }
+}
+
+// Without an SSID, brings down every active Wi-Fi connection except the
+// hotspot, only on `interface` when one is given
+fn disconnect_all(interface: Option<&str>) -> i32 {
+    let connections = match nmcli::connections() {
+        Ok(connections) => connections,
+        Err(e) => return fail(e, EXIT_FAILURE),
+    };
+    let active: Vec<_> = connections
+        .into_iter()
+        .filter(|c| c.active && c.kind == "802-11-wireless" && c.name != HOTSPOT_PROFILE)
+        .filter(|c| interface.is_none_or(|i| c.device == i))
+        .collect();
+    if active.is_empty() {
+        return fail("no active Wi-Fi connection", EXIT_NOT_FOUND);
+    }
+
+    // The history keys on the SSID, which need not match the profile name
+    let profiles = nmcli::wifi_profiles().unwrap_or_default();
+    let mut code = EXIT_OK;
+    for connection in active {
+        let ssid = profiles
+            .iter()
+            .find(|p| p.uuid == connection.uuid)
+            .map_or(connection.name.as_str(), |p| p.ssid.as_str());
+        let result = nmcli::connection_down(&connection.uuid);
+        state::record(Event::Disconnect, ssid, "", &result);
+        if let Err(e) = result {
+            code = fail(format!("{}: {e}", connection.name), EXIT_FAILURE);
+        }
+    }
+    code
+}
+
+fn disconnect(ssid: &str) -> i32 {
+    let active = nmcli::active_uuids();
+    if !nmcli::find_profiles(ssid)
+        .iter()
+        .any(|p| active.contains(&p.uuid))
+    {
+        return fail(format!("not connected to '{ssid}'"), EXIT_NOT_FOUND);
+    }
+    let result = nmcli::disconnect(ssid);
+    state::record(Event::Disconnect, ssid, "", &result);
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => fail(e, EXIT_FAILURE),
+    }
+}
+
+fn forget(ssid: &str) -> i32 {
+    let result = nmcli::forget(ssid);
+    if !matches!(result, Ok(0)) {
+        state::record(Event::Forget, ssid, "", &result.as_ref().map(|_| ()));
+    }
+    match result {
+        Ok(0) => fail(format!("no saved profile for '{ssid}'"), EXIT_NOT_FOUND),
+        Ok(_) => EXIT_OK,
+        Err(e) => fail(e, EXIT_FAILURE),
+    }
+}
+
+fn status(output: &Output, interface: Option<&str>) -> i32 {
+    if let Err(e) = output.validate(&DEVICE_FIELDS) {
+        return usage(&e);
+    }
+    let devices = match nmcli::devices() {
+        Ok(devices) => devices,
+        Err(e) => return fail(e, EXIT_FAILURE),
+    };
+    let wifi: Vec<_> = devices
+        .into_iter()
+        .filter(|d| d.kind == "wifi" && interface.is_none_or(|i| d.name == i))
+        .collect();
+    finish(output.print_status(&RadioState::read(), &wifi))
+}
@@ -0,0 +1,536 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+
+use crate::keymap::{self, Action, Keymap, Preset};
+use crate::network::{Bars, Column, Filters, SignalPart, SortMode};
+use crate::theme::{self, Color, Role, Theme};
+
+const DEFAULT_COLUMNS: [Column; 3] = [Column::Ssid, Column::Signal, Column::Security];
+const DEFAULT_SIGNAL_PARTS: [SignalPart; 2] = [SignalPart::Bars, SignalPart::Percent];
+const DEFAULT_STRONG_SIGNAL: u8 = 66;
+const DEFAULT_MEDIUM_SIGNAL: u8 = 33;
+const DEFAULT_THEME: &str = "dark";
+
+/// Where network operations are carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// NetworkManager through the nmcli command
+    #[default]
+    Nmcli,
+}
+
+/// Settings the interface starts with.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub backend: Backend,
+    /// Wi-Fi device to select first instead of the first one found.
+    pub interface: Option<String>,
+    pub sort: SortMode,
+    pub filters: Filters,
+    /// Name of a built-in theme or one from `themes`.
+    pub theme: String,
+    /// Themes defined in the config file.
+    pub themes: BTreeMap<String, Theme>,
+    /// Seconds between automatic rescans of the Wi-Fi list, 0 for never.
+    pub refresh_interval: u64,
+    pub columns: Vec<Column>,
+    /// Signal percentage from which a network counts as strong.
+    pub strong_signal: u8,
+    /// Signal percentage from which a network counts as medium.
+    pub medium_signal: u8,
+    /// What the signal column shows, in order.
+    pub signal_parts: Vec<SignalPart>,
+    /// Draw the signal bars with ASCII characters instead of block glyphs.
+    pub ascii_bars: bool,
+    /// Match the search query's characters in order rather than as one piece.
+    pub search_fuzzy: bool,
+    /// Show only the matching networks while searching, instead of jumping.
+    pub search_narrow: bool,
+    /// Select with clicks and scroll with the wheel.
+    pub mouse: bool,
+    pub keymap: Keymap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            backend: Backend::default(),
+            interface: None,
+            sort: SortMode::default(),
+            filters: Filters {
+                hidden: false,
+                open: false,
+                weak: false,
+                saved_only: false,
+                weak_below: DEFAULT_MEDIUM_SIGNAL,
+            },
+            theme: String::from(default_theme()),
+            themes: BTreeMap::new(),
+            refresh_interval: 0,
+            columns: DEFAULT_COLUMNS.to_vec(),
+            strong_signal: DEFAULT_STRONG_SIGNAL,
+            medium_signal: DEFAULT_MEDIUM_SIGNAL,
+            signal_parts: DEFAULT_SIGNAL_PARTS.to_vec(),
+            ascii_bars: !utf8_locale(),
+            search_fuzzy: false,
+            search_narrow: false,
+            mouse: true,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The theme named by `theme`, looking at the config file's first.
+    pub fn theme(&self) -> Option<Theme> {
+        self.themes
+            .get(&self.theme)
+            .cloned()
+            .or_else(|| Theme::built_in(&self.theme))
+    }
+
+    pub fn unknown_theme(&self) -> String {
+        let names: Vec<&str> = theme::BUILT_IN
+            .into_iter()
+            .chain(self.themes.keys().map(String::as_str))
+            .collect();
+        format!(
+            "unknown theme '{}', expected one of: {}",
+            self.theme,
+            names.join(", ")
+        )
+    }
+}
+
+// Layout of config.toml. Values checked beyond their type keep their span
+// so errors can point at the line.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    backend: Option<Backend>,
+    interface: Option<String>,
+    sort: Option<SortMode>,
+    theme: Option<Spanned<String>>,
+    refresh_interval: Option<u64>,
+    mouse: Option<bool>,
+    columns: Option<Spanned<Vec<Column>>>,
+    signal: Option<SignalConfig>,
+    filter: Option<FilterConfig>,
+    search: Option<SearchConfig>,
+    keymap: Option<Preset>,
+    #[serde(default)]
+    keys: BTreeMap<Spanned<String>, Spanned<KeyList>>,
+    #[serde(default)]
+    themes: BTreeMap<String, ThemeSpec>,
+}
+
+// Roles left out keep the style of the base theme
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ThemeSpec {
+    base: Option<Spanned<String>>,
+    header: Option<StyleSpec>,
+    border: Option<StyleSpec>,
+    footer: Option<StyleSpec>,
+    text: Option<StyleSpec>,
+    signal_strong: Option<StyleSpec>,
+    signal_medium: Option<StyleSpec>,
+    signal_weak: Option<StyleSpec>,
+    selected: Option<StyleSpec>,
+    connected: Option<StyleSpec>,
+    error: Option<StyleSpec>,
+    dialog: Option<StyleSpec>,
+    search: Option<StyleSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StyleSpec {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    bold: Option<bool>,
+    dim: Option<bool>,
+    reverse: Option<bool>,
+    underline: Option<bool>,
+}
+
+impl ThemeSpec {
+    fn styles(&self) -> [(Role, &Option<StyleSpec>); 12] {
+        [
+            (Role::Header, &self.header),
+            (Role::Border, &self.border),
+            (Role::Footer, &self.footer),
+            (Role::Text, &self.text),
+            (Role::SignalStrong, &self.signal_strong),
+            (Role::SignalMedium, &self.signal_medium),
+            (Role::SignalWeak, &self.signal_weak),
+            (Role::Selected, &self.selected),
+            (Role::Connected, &self.connected),
+            (Role::Error, &self.error),
+            (Role::Dialog, &self.dialog),
+            (Role::Search, &self.search),
+        ]
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignalConfig {
+    strong: Option<Spanned<u8>>,
+    medium: Option<Spanned<u8>>,
+    show: Option<Spanned<Vec<SignalPart>>>,
+    bars: Option<Bars>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilterConfig {
+    hidden: Option<bool>,
+    open: Option<bool>,
+    weak: Option<bool>,
+    saved: Option<bool>,
+    weak_below: Option<Spanned<u8>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchConfig {
+    fuzzy: Option<bool>,
+    narrow: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+// NO_COLOR (https://no-color.org) only changes the default, so a theme picked
+// in the config file or on the command line still applies
+fn default_theme() -> &'static str {
+    match std::env::var_os("NO_COLOR") {
+        Some(value) if !value.is_empty() => "monochrome",
+        _ => DEFAULT_THEME,
+    }
+}
+
+// The block glyphs of the signal bars need a UTF-8 locale
+fn utf8_locale() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|value| !value.is_empty())
+        .is_some_and(|value| {
+            let value = value.to_string_lossy().to_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        })
+}
+
+/// `$XDG_CONFIG_HOME/nmcurse/config.toml`, falling back to `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("nmcurse").join("config.toml"))
+}
+
+/// Reads the config file at `path`, or at the default location when `None`.
+///
+/// A missing file at the default location just means defaults; an explicit
+/// path must exist. Errors read `<file>:<line>: <problem>`.
+pub fn load(path: Option<&Path>) -> Result<Settings, String> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Settings::default()),
+        },
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Settings::default());
+        }
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    };
+    parse_file(&path, &contents)
+}
+
+// Turns the byte offset of an error into `<file>:<line>: `
+fn parse_file(path: &Path, contents: &str) -> Result<Settings, String> {
+    parse(contents).map_err(|(offset, message)| match offset {
+        Some(offset) => format!(
+            "{}:{}: {message}",
+            path.display(),
+            contents[..offset].matches('\n').count() + 1
+        ),
+        None => format!("{}: {message}", path.display()),
+    })
+}
+
+// Errors carry the byte offset they refer to, when known
+fn parse(contents: &str) -> Result<Settings, (Option<usize>, String)> {
+    let file: ConfigFile = toml::from_str(contents)
+        .map_err(|e| (e.span().map(|s| s.start), e.message().to_string()))?;
+    let at = |span: std::ops::Range<usize>, message: String| (Some(span.start), message);
+
+    let mut settings = Settings::default();
+    if let Some(backend) = file.backend {
+        settings.backend = backend;
+    }
+    settings.interface = file.interface;
+    if let Some(sort) = file.sort {
+        settings.sort = sort;
+    }
+
+    for (name, spec) in &file.themes {
+        let base = spec.base.as_ref();
+        let mut theme = match base {
+            Some(base) => Theme::built_in(base.get_ref()).ok_or_else(|| {
+                at(
+                    base.span(),
+                    format!(
+                        "unknown base theme '{}', expected one of: {}",
+                        base.get_ref(),
+                        theme::BUILT_IN.join(", ")
+                    ),
+                )
+            })?,
+            None => Theme::default(),
+        };
+        for (role, style) in spec.styles() {
+            let Some(style) = style else { continue };
+            let target = theme.style_mut(role);
+            target.fg = style.fg.unwrap_or(target.fg);
+            target.bg = style.bg.unwrap_or(target.bg);
+            target.bold = style.bold.unwrap_or(target.bold);
+            target.dim = style.dim.unwrap_or(target.dim);
+            target.reverse = style.reverse.unwrap_or(target.reverse);
+            target.underline = style.underline.unwrap_or(target.underline);
+        }
+        settings.themes.insert(name.clone(), theme);
+    }
+    if let Some(theme) = file.theme {
+        let span = theme.span();
+        settings.theme = theme.into_inner();
+        if settings.theme().is_none() {
+            return Err(at(span, settings.unknown_theme()));
+        }
+    }
+    if let Some(interval) = file.refresh_interval {
+        settings.refresh_interval = interval;
+    }
+    if let Some(mouse) = file.mouse {
+        settings.mouse = mouse;
+    }
+
+    if let Some(columns) = file.columns {
+        let span = columns.span();
+        let columns = columns.into_inner();
+        if columns.is_empty() {
+            return Err(at(span, String::from("columns must not be empty")));
+        }
+        if let Some(column) = columns
+            .iter()
+            .enumerate()
+            .find_map(|(i, c)| columns[..i].contains(c).then_some(c))
+        {
+            return Err(at(
+                span,
+                format!(
+                    "column {} is listed twice",
+                    format!("{column:?}").to_lowercase()
+                ),
+            ));
+        }
+        settings.columns = columns;
+    }
+
+    if let Some(signal) = file.signal {
+        for threshold in [&signal.strong, &signal.medium].into_iter().flatten() {
+            if *threshold.get_ref() > 100 {
+                return Err(at(
+                    threshold.span(),
+                    String::from("signal thresholds are percentages from 0 to 100"),
+                ));
+            }
+        }
+        let span = signal
+            .medium
+            .as_ref()
+            .or(signal.strong.as_ref())
+            .map(|s| s.span());
+        settings.strong_signal = signal
+            .strong
+            .map_or(DEFAULT_STRONG_SIGNAL, |s| s.into_inner());
+        settings.medium_signal = signal
+            .medium
+            .map_or(DEFAULT_MEDIUM_SIGNAL, |s| s.into_inner());
+        if settings.medium_signal >= settings.strong_signal {
+            return Err((
+                span.map(|s| s.start),
+                format!(
+                    "signal.medium ({}) must be below signal.strong ({})",
+                    settings.medium_signal, settings.strong_signal
+                ),
+            ));
+        }
+
+        if let Some(show) = signal.show {
+            let span = show.span();
+            let parts = show.into_inner();
+            if parts.is_empty() {
+                return Err(at(span, String::from("signal.show must not be empty")));
+            }
+            settings.signal_parts = parts;
+        }
+        match signal.bars {
+            Some(Bars::Ascii) => settings.ascii_bars = true,
+            Some(Bars::Unicode) => settings.ascii_bars = false,
+            Some(Bars::Auto) | None => {}
+        }
+    }
+
+    // The weak filter follows the medium threshold unless set apart
+    settings.filters.weak_below = settings.medium_signal;
+    if let Some(filter) = file.filter {
+        settings.filters.hidden = filter.hidden.unwrap_or(false);
+        settings.filters.open = filter.open.unwrap_or(false);
+        settings.filters.weak = filter.weak.unwrap_or(false);
+        settings.filters.saved_only = filter.saved.unwrap_or(false);
+        if let Some(weak_below) = filter.weak_below {
+            if *weak_below.get_ref() > 100 {
+                return Err(at(
+                    weak_below.span(),
+                    String::from("filter.weak_below is a percentage from 0 to 100"),
+                ));
+            }
+            settings.filters.weak_below = weak_below.into_inner();
+        }
+    }
+
+    if let Some(search) = file.search {
+        settings.search_fuzzy = search.fuzzy.unwrap_or(false);
+        settings.search_narrow = search.narrow.unwrap_or(false);
+    }
+
+    // Start from the preset, then unbind every configured action first so
+    // keys can be swapped
+    settings.keymap = Keymap::preset(file.keymap.unwrap_or_default());
+    let mut bindings = Vec::new();
+    for (name, keys) in &file.keys {
+        let action = Action::from_name(name.get_ref()).ok_or_else(|| {
+            let names: Vec<&str> = Action::ALL.iter().map(|a| a.name()).collect();
+            at(
+                name.span(),
+                format!(
+                    "unknown action '{}', expected one of: {}",
+                    name.get_ref(),
+                    names.join(", ")
+                ),
+            )
+        })?;
+        let specs = match keys.get_ref() {
+            KeyList::One(key) => vec![key.as_str()],
+            KeyList::Many(keys) => keys.iter().map(String::as_str).collect(),
+        };
+        let codes = specs
+            .into_iter()
+            .map(keymap::parse_keys)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| at(keys.span(), e))?;
+        settings.keymap.unbind(action);
+        bindings.push((action, codes, keys.span()));
+    }
+    for (action, codes, span) in bindings {
+        settings
+            .keymap
+            .bind(action, &codes)
+            .map_err(|e| at(span, e))?;
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(contents: &str) -> String {
+        parse_file(Path::new("config.toml"), contents).unwrap_err()
+    }
+
+    #[test]
+    fn empty_file_gives_defaults() {
+        let settings = parse_file(Path::new("config.toml"), "").unwrap();
+        assert_eq!(settings.columns, DEFAULT_COLUMNS);
+        assert_eq!(settings.strong_signal, DEFAULT_STRONG_SIGNAL);
+        assert_eq!(settings.medium_signal, DEFAULT_MEDIUM_SIGNAL);
+        assert_eq!(settings.filters.weak_below, DEFAULT_MEDIUM_SIGNAL);
+    }
+
+    #[test]
+    fn syntax_error_names_the_line() {
+        let message = error("mouse = true\nsort = \n");
+        assert!(message.starts_with("config.toml:2: "), "{message}");
+    }
+
+    #[test]
+    fn unknown_key_names_the_line() {
+        let message = error("mouse = true\n\nsorting = \"name\"\n");
+        assert!(message.starts_with("config.toml:3: "), "{message}");
+    }
+
+    #[test]
+    fn checked_values_name_their_line() {
+        let message = error("mouse = true\n[signal]\nstrong = 101\n");
+        assert_eq!(
+            message,
+            "config.toml:3: signal thresholds are percentages from 0 to 100"
+        );
+
+        let message = error("columns = []\n");
+        assert_eq!(message, "config.toml:1: columns must not be empty");
+    }
+
+    #[test]
+    fn medium_signal_must_be_below_strong() {
+        let message = error("[signal]\nstrong = 40\nmedium = 50\n");
+        assert_eq!(
+            message,
+            "config.toml:3: signal.medium (50) must be below signal.strong (40)"
+        );
+    }
+
+    #[test]
+    fn weak_filter_follows_medium_signal() {
+        let settings = parse_file(Path::new("config.toml"), "[signal]\nmedium = 20\n").unwrap();
+        assert_eq!(settings.filters.weak_below, 20);
+
+        let contents = "[signal]\nmedium = 20\n[filter]\nweak_below = 10\n";
+        let settings = parse_file(Path::new("config.toml"), contents).unwrap();
+        assert_eq!(settings.filters.weak_below, 10);
+    }
+
+    #[test]
+    fn unknown_theme_and_action_name_their_line() {
+        let message = error("\ntheme = \"purple\"\n");
+        assert!(
+            message.starts_with("config.toml:2: unknown theme 'purple'"),
+            "{message}"
+        );
+
+        let message = error("[keys]\nquit = \"q\"\nleave = \"x\"\n");
+        assert!(
+            message.starts_with("config.toml:3: unknown action 'leave'"),
+            "{message}"
+        );
+    }
+}
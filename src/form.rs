@@ -0,0 +1,260 @@
+use ncurses::*;
+use zeroize::Zeroizing;
+
+use super::network::{BACKSPACE_KEY, BACKSPACE_KEY_ALT, ENTER_KEY, ESCAPE_KEY};
+use super::theme::{self, Role};
+
+const TAB_KEY: i32 = 9;
+const FORM_MAX_WIDTH: i32 = 72;
+
+pub enum FieldKind {
+    /// Cycles through a fixed set of values with left/right or space.
+    Choice(&'static [&'static str]),
+    /// Free-form ASCII text.
+    Text,
+    /// Text shown masked while typed, such as a password.
+    Secret,
+}
+
+pub struct Field {
+    pub label: &'static str,
+    pub kind: FieldKind,
+    /// Zeroed on drop, since a field may hold a secret.
+    pub value: Zeroizing<String>,
+}
+
+/// A modal dialog editing a list of labelled fields.
+pub struct Form {
+    title: String,
+    fields: Vec<Field>,
+    current: usize,
+}
+
+impl Form {
+    pub fn new(title: &str) -> Self {
+        Form {
+            title: title.to_string(),
+            fields: Vec::new(),
+            current: 0,
+        }
+    }
+
+    pub fn choice(
+        mut self,
+        label: &'static str,
+        options: &'static [&'static str],
+        value: &str,
+    ) -> Self {
+        let value = if options.contains(&value) {
+            value.to_string()
+        } else {
+            options[0].to_string()
+        };
+        self.fields.push(Field {
+            label,
+            kind: FieldKind::Choice(options),
+            value: Zeroizing::new(value),
+        });
+        self
+    }
+
+    pub fn text(mut self, label: &'static str, value: &str) -> Self {
+        self.fields.push(Field {
+            label,
+            kind: FieldKind::Text,
+            value: Zeroizing::new(value.to_string()),
+        });
+        self
+    }
+
+    /// A text field shown masked, starting out empty.
+    pub fn secret(mut self, label: &'static str) -> Self {
+        // Room for any passphrase up front, so typing never reallocates and
+        // leaves a copy behind
+        self.fields.push(Field {
+            label,
+            kind: FieldKind::Secret,
+            value: Zeroizing::new(String::with_capacity(128)),
+        });
+        self
+    }
+
+    /// Returns the current value of the field with the given label.
+    pub fn value(&self, label: &str) -> &str {
+        self.fields
+            .iter()
+            .find(|f| f.label == label)
+            .map(|f| f.value.as_str())
+            .unwrap_or("")
+    }
+
+    /// Shows the form until it is submitted or cancelled.
+    ///
+    /// `validate` runs on Enter; an error keeps the form open and is shown
+    /// under the fields. Returns true when the form was submitted.
+    pub fn run<F>(&mut self, validate: F) -> bool
+    where
+        F: Fn(&Form) -> Result<(), String>,
+    {
+        if self.fields.is_empty() {
+            return false;
+        }
+
+        let height = self.fields.len() as i32 + 6;
+        let width = std::cmp::min(FORM_MAX_WIDTH, COLS() - 2);
+        let win = newwin(height, width, (LINES() - height) / 2, (COLS() - width) / 2);
+        keypad(win, true);
+
+        let mut error = String::new();
+        let submitted = loop {
+            self.draw(win, &error);
+            let ch = wgetch(win);
+            error.clear();
+
+            match ch {
+                ENTER_KEY | KEY_ENTER => match validate(self) {
+                    Ok(()) => break true,
+                    Err(e) => error = e,
+                },
+                ESCAPE_KEY => break false,
+                KEY_UP | KEY_BTAB => {
+                    self.current = (self.current + self.fields.len() - 1) % self.fields.len();
+                }
+                KEY_DOWN | TAB_KEY => {
+                    self.current = (self.current + 1) % self.fields.len();
+                }
+                KEY_LEFT => self.cycle_choice(false),
+                KEY_RIGHT => self.cycle_choice(true),
+                _ => self.edit_current(ch),
+            }
+        };
+
+        delwin(win);
+        submitted
+    }
+
+    fn cycle_choice(&mut self, forward: bool) {
+        let field = &mut self.fields[self.current];
+        if let FieldKind::Choice(options) = field.kind {
+            let pos = options.iter().position(|o| *o == *field.value).unwrap_or(0);
+            let next = if forward {
+                (pos + 1) % options.len()
+            } else {
+                (pos + options.len() - 1) % options.len()
+            };
+            *field.value = options[next].to_string();
+        }
+    }
+
+    fn edit_current(&mut self, ch: i32) {
+        if let FieldKind::Choice(_) = self.fields[self.current].kind {
+            if ch == ' ' as i32 {
+                self.cycle_choice(true);
+            }
+            return;
+        }
+
+        let value = &mut self.fields[self.current].value;
+        if ch == KEY_BACKSPACE || ch == BACKSPACE_KEY || ch == BACKSPACE_KEY_ALT {
+            value.pop();
+        } else if (0x20..0x7f).contains(&ch) {
+            value.push(ch as u8 as char);
+        }
+    }
+
+    fn draw(&self, win: WINDOW, error: &str) {
+        werase(win);
+        box_(win, 0, 0);
+        let width = getmaxx(win);
+
+        // Not mvwprintw, titles, values and errors may contain %
+        wattron(win, theme::style(Role::Dialog));
+        let _ = mvwaddstr(win, 0, 2, &format!(" {} ", self.title));
+        wattroff(win, theme::style(Role::Dialog));
+
+        let label_width = self.fields.iter().map(|f| f.label.len()).max().unwrap_or(0) + 2;
+        let value_width = std::cmp::max(0, width - label_width as i32 - 4) as usize;
+
+        for (i, field) in self.fields.iter().enumerate() {
+            let y = i as i32 + 2;
+            let _ = mvwaddstr(win, y, 2, &format!("{:<label_width$}", field.label));
+
+            let text = match field.kind {
+                FieldKind::Choice(_) => format!("< {} >", *field.value),
+                FieldKind::Text if field.value.len() > value_width => {
+                    // Keep the end of long values visible while typing
+                    field.value[field.value.len() - value_width..].to_string()
+                }
+                FieldKind::Text => field.value.to_string(),
+                FieldKind::Secret => "o".repeat(field.value.len().min(value_width)),
+            };
+
+            let attr = if i == self.current {
+                theme::selected(A_NORMAL())
+            } else {
+                A_NORMAL()
+            };
+            wattron(win, attr);
+            let _ = mvwaddstr(
+                win,
+                y,
+                2 + label_width as i32,
+                &format!("{:<value_width$}", text),
+            );
+            wattroff(win, attr);
+        }
+
+        let status_y = self.fields.len() as i32 + 3;
+        if error.is_empty() {
+            let _ = mvwaddstr(
+                win,
+                status_y,
+                2,
+                "[Tab: Next, ←/→: Change, Enter: Save, Esc: Cancel]",
+            );
+        } else {
+            wattron(win, theme::style(Role::Error));
+            let _ = mvwaddstr(win, status_y, 2, error);
+            wattroff(win, theme::style(Role::Error));
+        }
+
+        wrefresh(win);
+    }
+}
+
+/// Shows a boxed message and waits for a key press.
+pub fn show_message(title: &str, message: &str) {
+    message_box(title, message);
+}
+
+/// Shows a boxed question and returns true only when it is answered with y.
+pub fn confirm(title: &str, message: &str) -> bool {
+    let answer = message_box(title, &format!("{message}\n\n[y: Yes, any other key: No]"));
+    answer == 'y' as i32 || answer == 'Y' as i32
+}
+
+// Draws a centered box with the message and returns the key that closed it
+fn message_box(title: &str, message: &str) -> i32 {
+    let lines: Vec<&str> = message.lines().collect();
+    let text_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as i32;
+    let width = std::cmp::min(
+        std::cmp::max(text_width, title.len() as i32 + 2) + 4,
+        COLS() - 2,
+    );
+    let height = lines.len() as i32 + 4;
+    let win = newwin(height, width, (LINES() - height) / 2, (COLS() - width) / 2);
+
+    box_(win, 0, 0);
+    // Not mvwprintw, messages quote SSIDs and nmcli errors that may contain %
+    wattron(win, theme::style(Role::Dialog));
+    let _ = mvwaddstr(win, 0, 2, &format!(" {} ", title));
+    wattroff(win, theme::style(Role::Dialog));
+    for (i, line) in lines.iter().enumerate() {
+        let _ = mvwaddstr(win, i as i32 + 2, 2, line);
+    }
+    wrefresh(win);
+
+    let key = wgetch(win);
+    delwin(win);
+    key
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use zeroize::Zeroizing;
+
+use crate::network::NetworkError;
+use crate::nmcli;
+
+/// Name of the profile nmcurse creates for its access point.
+pub const HOTSPOT_PROFILE: &str = "nmcurse-hotspot";
+
+pub const BANDS: [&str; 2] = ["bg", "a"];
+pub const SECURITY_MODES: [&str; 2] = ["wpa2", "wpa3"];
+
+/// Settings entered in the hotspot dialog.
+#[derive(Debug, Clone)]
+pub struct HotspotConfig {
+    pub ssid: String,
+    /// `bg` for 2.4 GHz or `a` for 5 GHz.
+    pub band: String,
+    /// Empty to let NetworkManager pick a channel.
+    pub channel: String,
+    /// `wpa2` or `wpa3`.
+    pub security: String,
+    pub password: Zeroizing<String>,
+    /// Connection to bring up first so the hotspot has something to share.
+    pub upstream: String,
+}
+
+impl HotspotConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ssid.is_empty() || self.ssid.len() > 32 {
+            return Err(String::from("SSID must be 1-32 bytes"));
+        }
+        if !(8..=63).contains(&self.password.len()) {
+            return Err(String::from("Password must be 8-63 characters"));
+        }
+        if !self.channel.is_empty() {
+            let valid = match (self.band.as_str(), self.channel.parse::<u32>()) {
+                ("bg", Ok(channel)) => (1..=14).contains(&channel),
+                ("a", Ok(channel)) => (32..=177).contains(&channel),
+                _ => false,
+            };
+            if !valid {
+                return Err(format!(
+                    "Channel {} is not valid for band {}",
+                    self.channel, self.band
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A device associated with the hotspot.
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub mac: String,
+    pub ip: String,
+    pub hostname: String,
+}
+
+/// Creates (or recreates) the hotspot profile on `device` and activates it.
+pub fn start(device: &str, config: &HotspotConfig) -> Result<(), NetworkError> {
+    if !config.upstream.is_empty() && !nmcli::active_names().contains(&config.upstream) {
+        nmcli::run(&["connection", "up", "id", &config.upstream])?;
+    }
+
+    // Start from a clean profile so stale settings from an earlier run never leak in
+    let _ = nmcli::run(&["connection", "delete", "id", HOTSPOT_PROFILE]);
+
+    let key_mgmt = if config.security == "wpa3" {
+        "sae"
+    } else {
+        "wpa-psk"
+    };
+    let mut args = vec![
+        "connection",
+        "add",
+        "type",
+        "wifi",
+        "ifname",
+        device,
+        "con-name",
+        HOTSPOT_PROFILE,
+        "autoconnect",
+        "no",
+        "ssid",
+        &config.ssid,
+        "802-11-wireless.mode",
+        "ap",
+        "802-11-wireless.band",
+        &config.band,
+        "ipv4.method",
+        "shared",
+        "ipv6.method",
+        "shared",
+        "wifi-sec.key-mgmt",
+        key_mgmt,
+    ];
+    if !config.channel.is_empty() {
+        args.extend(["802-11-wireless.channel", &config.channel]);
+    }
+    let uuid = nmcli::add_connection(&args)?;
+    // The password is handed over in a private file, never on the command line
+    nmcli::connection_up_with_secrets(
+        &uuid,
+        None,
+        &[("802-11-wireless-security.psk", &config.password)],
+    )
+}
+
+/// Deactivates the hotspot. The profile stays until the next `start`
+/// replaces it.
+pub fn stop() -> Result<(), NetworkError> {
+    nmcli::run(&["connection", "down", "id", HOTSPOT_PROFILE]).map(|_| ())
+}
+
+/// Returns the device the hotspot runs on, if it is active.
+pub fn active_device() -> Option<String> {
+    nmcli::connections()
+        .ok()?
+        .into_iter()
+        .find(|c| c.name == HOTSPOT_PROFILE && c.active)
+        .map(|c| c.device)
+}
+
+/// Picks the connection the hotspot would share: the first active one that
+/// is not on the hotspot device itself.
+pub fn default_upstream(device: &str) -> String {
+    nmcli::connections()
+        .unwrap_or_default()
+        .into_iter()
+        .find(|c| {
+            c.active
+                && c.device != device
+                && c.name != HOTSPOT_PROFILE
+                && !matches!(c.kind.as_str(), "loopback" | "bridge")
+        })
+        .map(|c| c.name)
+        .unwrap_or_default()
+}
+
+/// Lists clients of the hotspot on `device`.
+///
+/// NetworkManager's dnsmasq writes DHCP leases with hostnames; without them
+/// the kernel neighbour table still shows who is associated.
+pub fn clients(device: &str) -> Vec<Client> {
+    let leases = format!("/var/lib/NetworkManager/dnsmasq-{device}.leases");
+    if let Ok(contents) = fs::read_to_string(leases) {
+        // "<expiry> <mac> <ip> <hostname> <client id>"
+        let clients: Vec<Client> = contents
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                (fields.len() >= 4).then(|| Client {
+                    mac: fields[1].to_string(),
+                    ip: fields[2].to_string(),
+                    hostname: if fields[3] == "*" {
+                        String::new()
+                    } else {
+                        fields[3].to_string()
+                    },
+                })
+            })
+            .collect();
+        if !clients.is_empty() {
+            return clients;
+        }
+    }
+
+    // "IP address  HW type  Flags  HW address  Mask  Device"
+    fs::read_to_string("/proc/net/arp")
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            (fields.len() >= 6 && fields[5] == device && fields[3] != "00:00:00:00:00:00").then(
+                || Client {
+                    mac: fields[3].to_string(),
+                    ip: fields[0].to_string(),
+                    hostname: String::new(),
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,437 @@
+use std::net::IpAddr;
+
+use crate::network::NetworkError;
+use crate::nmcli;
+
+pub const IPV4_METHODS: [&str; 5] = ["auto", "manual", "link-local", "shared", "disabled"];
+pub const IPV6_METHODS: [&str; 6] = ["auto", "dhcp", "manual", "link-local", "ignore", "disabled"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    fn setting(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "ipv4",
+            IpFamily::V6 => "ipv6",
+        }
+    }
+
+    fn max_prefix(self) -> u8 {
+        match self {
+            IpFamily::V4 => 32,
+            IpFamily::V6 => 128,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "IPv4",
+            IpFamily::V6 => "IPv6",
+        }
+    }
+
+    fn matches(self, addr: &IpAddr) -> bool {
+        matches!(
+            (self, addr),
+            (IpFamily::V4, IpAddr::V4(_)) | (IpFamily::V6, IpAddr::V6(_))
+        )
+    }
+}
+
+/// The ipv4 or ipv6 setting of a connection profile.
+///
+/// List values are kept as the comma separated strings the user edits, the
+/// same syntax `nmcli connection modify` accepts.
+#[derive(Debug, Clone)]
+pub struct IpConfig {
+    pub family: IpFamily,
+    pub method: String,
+    pub addresses: String,
+    pub gateway: String,
+    pub dns: String,
+    pub dns_search: String,
+    pub routes: String,
+}
+
+impl IpConfig {
+    fn properties(family: IpFamily) -> [String; 6] {
+        let setting = family.setting();
+        [
+            format!("{setting}.method"),
+            format!("{setting}.addresses"),
+            format!("{setting}.gateway"),
+            format!("{setting}.dns"),
+            format!("{setting}.dns-search"),
+            format!("{setting}.routes"),
+        ]
+    }
+
+    pub fn load(id: &str, family: IpFamily) -> Result<Self, NetworkError> {
+        let properties = Self::properties(family);
+        let fields: Vec<&str> = properties.iter().map(String::as_str).collect();
+        let values = nmcli::connection_fields(id, &fields)?;
+        Ok(IpConfig {
+            family,
+            method: values[0].clone(),
+            addresses: values[1].clone(),
+            gateway: values[2].clone(),
+            dns: values[3].clone(),
+            dns_search: values[4].clone(),
+            routes: routes_from_nmcli(&values[5]),
+        })
+    }
+
+    /// Applies several configurations (usually ipv4 and ipv6) in a single
+    /// `nmcli connection modify` call so a rejected value changes nothing.
+    pub fn apply(id: &str, configs: &[IpConfig]) -> Result<(), NetworkError> {
+        let mut changes = Vec::new();
+        for config in configs {
+            let properties = Self::properties(config.family);
+            let values = [
+                config.method.clone(),
+                normalize_list(&config.addresses),
+                config.gateway.trim().to_string(),
+                normalize_list(&config.dns),
+                normalize_list(&config.dns_search),
+                normalize_list(&config.routes),
+            ];
+            changes.extend(properties.into_iter().zip(values));
+        }
+
+        let changes: Vec<(&str, &str)> = changes
+            .iter()
+            .map(|(property, value)| (property.as_str(), value.as_str()))
+            .collect();
+        nmcli::modify_connection(id, &changes)
+    }
+
+    /// Checks the configuration before it is handed to NetworkManager.
+    pub fn validate(&self) -> Result<(), String> {
+        let family = self.family.name();
+        let addresses = split_list(&self.addresses);
+
+        match self.method.as_str() {
+            "manual" if addresses.is_empty() => {
+                return Err(format!(
+                    "{family}: manual method needs at least one address"
+                ));
+            }
+            "manual" | "auto" | "dhcp" => {}
+            // Addresses pick the subnet handed out to clients, there is no
+            // upstream gateway
+            "shared" if !self.gateway.trim().is_empty() => {
+                return Err(format!("{family}: the shared method takes no gateway"));
+            }
+            "shared" => {}
+            _ if !addresses.is_empty() || !self.gateway.trim().is_empty() => {
+                return Err(format!(
+                    "{family}: addresses need the auto, manual or shared method, \
+                     a gateway auto or manual"
+                ));
+            }
+            _ => {}
+        }
+
+        for address in &addresses {
+            self.parse_prefixed(address, true)
+                .map_err(|e| format!("{family} address {address}: {e}"))?;
+        }
+
+        let gateway = self.gateway.trim();
+        if !gateway.is_empty() {
+            if addresses.is_empty() {
+                return Err(format!("{family}: a gateway needs a static address"));
+            }
+            self.parse_addr(gateway)
+                .map_err(|e| format!("{family} gateway: {e}"))?;
+        }
+
+        for server in split_list(&self.dns) {
+            self.parse_addr(server)
+                .map_err(|e| format!("{family} DNS {server}: {e}"))?;
+        }
+
+        for domain in split_list(&self.dns_search) {
+            if !is_domain_name(domain) {
+                return Err(format!("{family} search domain {domain} is not valid"));
+            }
+        }
+
+        for route in split_list(&self.routes) {
+            self.validate_route(route)
+                .map_err(|e| format!("{family} route {route}: {e}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_addr(&self, value: &str) -> Result<IpAddr, String> {
+        let addr: IpAddr = value
+            .parse()
+            .map_err(|_| String::from("not an IP address"))?;
+        if !self.family.matches(&addr) {
+            return Err(format!("not an {} address", self.family.name()));
+        }
+        Ok(addr)
+    }
+
+    fn parse_prefixed(&self, value: &str, require_prefix: bool) -> Result<(), String> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        self.parse_addr(addr)?;
+        match prefix {
+            Some(prefix) => match prefix.parse::<u8>() {
+                Ok(p) if p <= self.family.max_prefix() => Ok(()),
+                _ => Err(format!(
+                    "prefix must be between 0 and {}",
+                    self.family.max_prefix()
+                )),
+            },
+            None if require_prefix => Err(String::from("missing /prefix")),
+            None => Ok(()),
+        }
+    }
+
+    // Routes use nmcli's "dest/prefix [next-hop] [metric]" syntax
+    fn validate_route(&self, route: &str) -> Result<(), String> {
+        let mut parts = route.split_whitespace();
+        let dest = parts.next().ok_or_else(|| String::from("empty route"))?;
+        self.parse_prefixed(dest, false)?;
+
+        let rest: Vec<&str> = parts.collect();
+        match rest.as_slice() {
+            [] => Ok(()),
+            [one] if one.parse::<u32>().is_ok() => Ok(()),
+            [one] => self.parse_addr(one).map(|_| ()),
+            [hop, metric] => {
+                self.parse_addr(hop)?;
+                metric
+                    .parse::<u32>()
+                    .map(|_| ())
+                    .map_err(|_| String::from("metric must be a number"))
+            }
+            _ => Err(String::from("expected dest/prefix [next-hop] [metric]")),
+        }
+    }
+}
+
+// Newer nmcli versions print routes as "{ ip = 10.0.0.0/8, nh = 10.0.0.1, mt = 100 }",
+// several joined by "; " or ", ", while `connection modify` only accepts
+// "10.0.0.0/8 10.0.0.1 100"
+fn routes_from_nmcli(value: &str) -> String {
+    if !value.contains('{') {
+        return value.to_string();
+    }
+
+    value
+        .split('}')
+        .filter_map(|group| {
+            let group = group
+                .trim_start_matches([',', ';', ' '])
+                .strip_prefix('{')?;
+            let mut route = Vec::new();
+            for attr in group.split(',') {
+                if let Some((key, val)) = attr.split_once('=') {
+                    if matches!(key.trim(), "ip" | "nh" | "mt") {
+                        route.push(val.trim());
+                    }
+                }
+            }
+            Some(route.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn split_list(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn normalize_list(value: &str) -> String {
+    split_list(value).join(",")
+}
+
+fn is_domain_name(value: &str) -> bool {
+    value.len() <= 253
+        && value.trim_end_matches('.').split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(family: IpFamily, method: &str) -> IpConfig {
+        IpConfig {
+            family,
+            method: String::from(method),
+            addresses: String::new(),
+            gateway: String::new(),
+            dns: String::new(),
+            dns_search: String::new(),
+            routes: String::new(),
+        }
+    }
+
+    fn with_addresses(family: IpFamily, addresses: &str) -> IpConfig {
+        IpConfig {
+            addresses: String::from(addresses),
+            ..config(family, "manual")
+        }
+    }
+
+    #[test]
+    fn checks_prefix_bounds() {
+        assert!(with_addresses(IpFamily::V4, "10.0.0.2/0")
+            .validate()
+            .is_ok());
+        assert!(with_addresses(IpFamily::V4, "10.0.0.2/32")
+            .validate()
+            .is_ok());
+        assert_eq!(
+            with_addresses(IpFamily::V4, "10.0.0.2/33").validate(),
+            Err(String::from(
+                "IPv4 address 10.0.0.2/33: prefix must be between 0 and 32"
+            ))
+        );
+        assert!(with_addresses(IpFamily::V6, "fd00::2/128")
+            .validate()
+            .is_ok());
+        assert!(with_addresses(IpFamily::V6, "fd00::2/129")
+            .validate()
+            .is_err());
+        assert!(with_addresses(IpFamily::V4, "10.0.0.2/x")
+            .validate()
+            .is_err());
+        assert_eq!(
+            with_addresses(IpFamily::V4, "10.0.0.2").validate(),
+            Err(String::from("IPv4 address 10.0.0.2: missing /prefix"))
+        );
+    }
+
+    #[test]
+    fn rejects_the_other_family() {
+        assert_eq!(
+            with_addresses(IpFamily::V4, "fd00::2/64").validate(),
+            Err(String::from("IPv4 address fd00::2/64: not an IPv4 address"))
+        );
+        assert!(with_addresses(IpFamily::V6, "10.0.0.2/24")
+            .validate()
+            .is_err());
+
+        let dns = IpConfig {
+            dns: String::from("1.1.1.1, 2606:4700::1111"),
+            ..config(IpFamily::V4, "auto")
+        };
+        assert_eq!(
+            dns.validate(),
+            Err(String::from(
+                "IPv4 DNS 2606:4700::1111: not an IPv4 address"
+            ))
+        );
+    }
+
+    #[test]
+    fn needs_an_address_for_a_gateway() {
+        let gateway = IpConfig {
+            gateway: String::from("10.0.0.1"),
+            ..config(IpFamily::V4, "auto")
+        };
+        assert_eq!(
+            gateway.validate(),
+            Err(String::from("IPv4: a gateway needs a static address"))
+        );
+
+        let gateway = IpConfig {
+            gateway: String::from("10.0.0.1"),
+            ..with_addresses(IpFamily::V4, "10.0.0.2/24")
+        };
+        assert!(gateway.validate().is_ok());
+        assert!(config(IpFamily::V4, "manual").validate().is_err());
+    }
+
+    #[test]
+    fn allows_addresses_only_where_they_apply() {
+        let shared = IpConfig {
+            addresses: String::from("192.168.50.1/24"),
+            ..config(IpFamily::V4, "shared")
+        };
+        assert!(shared.validate().is_ok());
+
+        let shared = IpConfig {
+            gateway: String::from("192.168.50.254"),
+            ..shared
+        };
+        assert_eq!(
+            shared.validate(),
+            Err(String::from("IPv4: the shared method takes no gateway"))
+        );
+
+        let disabled = IpConfig {
+            addresses: String::from("10.0.0.2/24"),
+            ..config(IpFamily::V4, "disabled")
+        };
+        assert!(disabled.validate().is_err());
+    }
+
+    #[test]
+    fn checks_routes() {
+        let routes = |routes: &str| {
+            IpConfig {
+                routes: String::from(routes),
+                ..config(IpFamily::V4, "auto")
+            }
+            .validate()
+        };
+        assert!(routes("10.0.0.0/8").is_ok());
+        assert!(routes("10.0.0.0/8 100").is_ok());
+        assert!(routes("10.0.0.0/8 192.168.1.1, 172.16.0.0/12 192.168.1.1 50").is_ok());
+        assert!(routes("10.0.0.0/33").is_err());
+        assert!(routes("10.0.0.0/8 fd00::1").is_err());
+        assert!(routes("10.0.0.0/8 gateway").is_err());
+        assert_eq!(
+            routes("10.0.0.0/8 192.168.1.1 high"),
+            Err(String::from(
+                "IPv4 route 10.0.0.0/8 192.168.1.1 high: metric must be a number"
+            ))
+        );
+        assert_eq!(
+            routes("10.0.0.0/8 192.168.1.1 50 extra"),
+            Err(String::from(
+                "IPv4 route 10.0.0.0/8 192.168.1.1 50 extra: \
+                 expected dest/prefix [next-hop] [metric]"
+            ))
+        );
+    }
+
+    #[test]
+    fn converts_routes_printed_by_newer_nmcli() {
+        assert_eq!(
+            routes_from_nmcli("10.0.0.0/8 10.0.0.1 100"),
+            "10.0.0.0/8 10.0.0.1 100"
+        );
+        assert_eq!(
+            routes_from_nmcli(
+                "{ ip = 10.0.0.0/8, nh = 10.0.0.1, mt = 100 }; \
+                 { ip = 172.16.0.0/12, table = 5 }"
+            ),
+            "10.0.0.0/8 10.0.0.1 100, 172.16.0.0/12"
+        );
+        assert_eq!(routes_from_nmcli(""), "");
+    }
+}
@@ -0,0 +1,466 @@
+use serde::Deserialize;
+
+use ncurses::{KEY_DOWN, KEY_END, KEY_HOME, KEY_NPAGE, KEY_PPAGE, KEY_UP};
+
+use crate::network::{ENTER_KEY, ESCAPE_KEY, TAB_KEY};
+
+/// Added to a key pressed with Alt (sent by terminals as Escape first).
+pub const ALT: i32 = 0x1000;
+
+/// Something the user can ask for from one of the lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NextView,
+    Rescan,
+    Radio,
+    NextDevice,
+    Hotspot,
+    Share,
+    Wps,
+    Disconnect,
+    Forget,
+    Edit,
+    IpSettings,
+    Import,
+    Details,
+    Search,
+    SearchNext,
+    SearchPrevious,
+    Sort,
+    FilterHidden,
+    FilterOpen,
+    FilterWeak,
+    FilterSaved,
+    Favorite,
+    Connect,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 30] = [
+        Action::NextView,
+        Action::Rescan,
+        Action::Radio,
+        Action::NextDevice,
+        Action::Hotspot,
+        Action::Share,
+        Action::Wps,
+        Action::Disconnect,
+        Action::Forget,
+        Action::Edit,
+        Action::IpSettings,
+        Action::Import,
+        Action::Details,
+        Action::Search,
+        Action::SearchNext,
+        Action::SearchPrevious,
+        Action::Sort,
+        Action::FilterHidden,
+        Action::FilterOpen,
+        Action::FilterWeak,
+        Action::FilterSaved,
+        Action::Favorite,
+        Action::Connect,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Quit,
+    ];
+
+    /// Name used in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NextView => "next-view",
+            Action::Rescan => "rescan",
+            Action::Radio => "radio",
+            Action::NextDevice => "next-device",
+            Action::Hotspot => "hotspot",
+            Action::Share => "share",
+            Action::Wps => "wps",
+            Action::Disconnect => "disconnect",
+            Action::Forget => "forget",
+            Action::Edit => "edit",
+            Action::IpSettings => "ip-settings",
+            Action::Import => "import",
+            Action::Details => "details",
+            Action::Search => "search",
+            Action::SearchNext => "search-next",
+            Action::SearchPrevious => "search-previous",
+            Action::Sort => "sort",
+            Action::FilterHidden => "filter-hidden",
+            Action::FilterOpen => "filter-open",
+            Action::FilterWeak => "filter-weak",
+            Action::FilterSaved => "filter-saved",
+            Action::Favorite => "favorite",
+            Action::Connect => "connect",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page-up",
+            Action::PageDown => "page-down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// Built-in key maps. Vim and Emacs add their movement keys to the default one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+/// Result of looking up the keys typed so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Action(Action),
+    /// The keys start a longer sequence, wait for the next one.
+    Pending,
+    Unbound,
+}
+
+// Named keys accepted in the config file besides single characters
+const NAMED_KEYS: [(&str, i32); 10] = [
+    ("enter", ENTER_KEY),
+    ("tab", TAB_KEY),
+    ("esc", ESCAPE_KEY),
+    ("space", b' ' as i32),
+    ("up", KEY_UP),
+    ("down", KEY_DOWN),
+    ("pageup", KEY_PPAGE),
+    ("pagedown", KEY_NPAGE),
+    ("home", KEY_HOME),
+    ("end", KEY_END),
+];
+
+/// Maps keys and key sequences to actions.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<i32>, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(Preset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: Preset) -> Keymap {
+        let mut keymap = Keymap {
+            bindings: Vec::new(),
+        };
+        let defaults = [
+            ("tab", Action::NextView),
+            ("r", Action::Rescan),
+            ("t", Action::Radio),
+            ("w", Action::NextDevice),
+            ("h", Action::Hotspot),
+            ("s", Action::Share),
+            ("p", Action::Wps),
+            ("d", Action::Disconnect),
+            ("f", Action::Forget),
+            ("e", Action::Edit),
+            ("i", Action::IpSettings),
+            ("a", Action::Import),
+            ("v", Action::Details),
+            ("/", Action::Search),
+            ("n", Action::SearchNext),
+            ("N", Action::SearchPrevious),
+            ("o", Action::Sort),
+            ("H", Action::FilterHidden),
+            ("O", Action::FilterOpen),
+            ("W", Action::FilterWeak),
+            ("S", Action::FilterSaved),
+            ("*", Action::Favorite),
+            ("enter", Action::Connect),
+            ("up", Action::Up),
+            ("down", Action::Down),
+            ("pageup", Action::PageUp),
+            ("pagedown", Action::PageDown),
+            ("home", Action::Top),
+            ("end", Action::Bottom),
+            ("q", Action::Quit),
+            ("esc", Action::Quit),
+        ];
+        let extra: &[(&str, Action)] = match preset {
+            Preset::Default => &[],
+            Preset::Vim => &[
+                ("j", Action::Down),
+                ("k", Action::Up),
+                ("C-f", Action::PageDown),
+                ("C-d", Action::PageDown),
+                ("C-b", Action::PageUp),
+                ("C-u", Action::PageUp),
+                ("g g", Action::Top),
+                ("G", Action::Bottom),
+            ],
+            Preset::Emacs => &[
+                ("C-s", Action::Search),
+                ("C-n", Action::Down),
+                ("C-p", Action::Up),
+                ("C-v", Action::PageDown),
+                ("M-v", Action::PageUp),
+                ("M-<", Action::Top),
+                ("M->", Action::Bottom),
+                ("C-g", Action::Quit),
+                ("C-x C-c", Action::Quit),
+            ],
+        };
+        for (spec, action) in defaults.iter().chain(extra) {
+            let keys = parse_keys(spec).expect("built-in key specs are valid");
+            keymap
+                .bind(*action, &[keys])
+                .expect("built-in key maps have no conflicts");
+        }
+        keymap
+    }
+
+    /// Looks up the keys typed since the last action.
+    pub fn lookup(&self, keys: &[i32]) -> Lookup {
+        let mut pending = false;
+        for (sequence, action) in &self.bindings {
+            if sequence == keys {
+                return Lookup::Action(*action);
+            }
+            pending |= sequence.starts_with(keys);
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Removes every key of `action`.
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|(_, a)| *a != action);
+    }
+
+    /// Adds key sequences for `action`. Fails if a sequence already does
+    /// something else or one of them is the start of the other.
+    pub fn bind(&mut self, action: Action, sequences: &[Vec<i32>]) -> Result<(), String> {
+        for sequence in sequences {
+            let clash = self
+                .bindings
+                .iter()
+                .find(|(other, _)| other.starts_with(sequence) || sequence.starts_with(other));
+            if let Some((other, other_action)) = clash {
+                return Err(if other == sequence {
+                    format!(
+                        "key '{}' is already bound to {}",
+                        sequence_name(sequence),
+                        other_action.name()
+                    )
+                } else {
+                    format!(
+                        "key '{}' overlaps '{}' of {}",
+                        sequence_name(sequence),
+                        sequence_name(other),
+                        other_action.name()
+                    )
+                });
+            }
+            self.bindings.push((sequence.clone(), action));
+        }
+        Ok(())
+    }
+
+    /// Name of the first key sequence bound to `action`, as shown to the user.
+    pub fn key_for(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(keys, _)| sequence_name(keys))
+    }
+
+    /// Footer text with the first key of each action, e.g. `[r: Rescan, q: Quit]`.
+    /// Unbound actions are left out.
+    pub fn footer(&self, entries: &[(Action, &str)]) -> String {
+        let entries: Vec<String> = entries
+            .iter()
+            .filter_map(|(action, label)| Some(format!("{}: {label}", self.key_for(*action)?)))
+            .collect();
+        format!("[{}]", entries.join(", "))
+    }
+}
+
+/// Parses a key sequence as written in the config file.
+///
+/// Keys are separated by spaces. Each is a single character, a name such as
+/// `enter` or `tab`, `C-x` for Ctrl or `M-x` for Alt; several plain
+/// characters written together (`gg`) are typed one after the other.
+pub fn parse_keys(spec: &str) -> Result<Vec<i32>, String> {
+    let mut keys = Vec::new();
+    for token in spec.split_whitespace() {
+        keys.extend(parse_token(token)?);
+    }
+    if keys.is_empty() {
+        return Err(String::from("empty key"));
+    }
+    Ok(keys)
+}
+
+fn parse_token(token: &str) -> Result<Vec<i32>, String> {
+    if let Some((_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(token))
+    {
+        return Ok(vec![*key]);
+    }
+
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_graphic() => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(c) = token.strip_prefix("C-").and_then(single) {
+        if c.is_ascii_alphabetic() || "@[\\]^_".contains(c) {
+            return Ok(vec![(c.to_ascii_lowercase() as i32) & 0x1f]);
+        }
+        // Not read as the three characters C, - and c
+        return Err(format!(
+            "key '{token}' has no Ctrl code, use C- with a letter or one of @[\\]^_"
+        ));
+    }
+    if let Some(c) = token.strip_prefix("M-").and_then(single) {
+        return Ok(vec![ALT | c as i32]);
+    }
+    if token.chars().all(|c| c.is_ascii_graphic()) {
+        return Ok(token.chars().map(|c| c as i32).collect());
+    }
+
+    let names: Vec<&str> = NAMED_KEYS.iter().map(|(name, _)| *name).collect();
+    Err(format!(
+        "unknown key '{token}', use a character, C-x, M-x or one of: {}",
+        names.join(", ")
+    ))
+}
+
+fn key_name(key: i32) -> String {
+    match NAMED_KEYS.iter().find(|(_, k)| *k == key) {
+        Some(_) if key == TAB_KEY => String::from("Tab"),
+        Some((name, _)) => name.to_string(),
+        None if key & ALT != 0 => format!("M-{}", key_name(key & !ALT)),
+        None if (1..=31).contains(&key) => format!("C-{}", ((key as u8) | 0x60) as char),
+        None => char::from_u32(key as u32)
+            .map(String::from)
+            .unwrap_or_else(|| key.to_string()),
+    }
+}
+
+fn sequence_name(keys: &[i32]) -> String {
+    keys.iter()
+        .map(|k| key_name(*k))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_characters_and_named_keys() {
+        assert_eq!(parse_keys("q"), Ok(vec![b'q' as i32]));
+        assert_eq!(parse_keys("Enter"), Ok(vec![ENTER_KEY]));
+        assert_eq!(parse_keys("pagedown"), Ok(vec![KEY_NPAGE]));
+        assert_eq!(parse_keys("space"), Ok(vec![b' ' as i32]));
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(parse_keys("C-x"), Ok(vec![0x18]));
+        assert_eq!(parse_keys("C-X"), Ok(vec![0x18]));
+        assert_eq!(parse_keys("M-v"), Ok(vec![ALT | b'v' as i32]));
+        assert_eq!(parse_keys("M-<"), Ok(vec![ALT | b'<' as i32]));
+    }
+
+    #[test]
+    fn parses_sequences() {
+        assert_eq!(parse_keys("gg"), Ok(vec![b'g' as i32; 2]));
+        assert_eq!(parse_keys("g g"), Ok(vec![b'g' as i32; 2]));
+        assert_eq!(parse_keys("C-x C-c"), Ok(vec![0x18, 0x03]));
+    }
+
+    #[test]
+    fn rejects_empty_and_unknown_keys() {
+        assert_eq!(parse_keys("  "), Err(String::from("empty key")));
+        assert!(parse_keys("é").unwrap_err().starts_with("unknown key 'é'"));
+        // Only letters and a few symbols have a Ctrl code
+        assert!(parse_keys("C-1").unwrap_err().contains("no Ctrl code"));
+        assert_eq!(parse_keys("C-"), Ok(vec![b'C' as i32, b'-' as i32]));
+    }
+
+    #[test]
+    fn names_keys_as_they_are_written() {
+        for spec in ["q", "Tab", "C-x C-c", "M-v", "pagedown"] {
+            assert_eq!(sequence_name(&parse_keys(spec).unwrap()), spec);
+        }
+    }
+
+    #[test]
+    fn built_in_presets_have_no_conflicts() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Emacs] {
+            Keymap::preset(preset);
+        }
+    }
+
+    #[test]
+    fn rejects_a_key_bound_twice() {
+        let mut keymap = Keymap::default();
+        let error = keymap
+            .bind(Action::Sort, &[parse_keys("q").unwrap()])
+            .unwrap_err();
+        assert_eq!(error, "key 'q' is already bound to quit");
+
+        keymap.unbind(Action::Quit);
+        assert_eq!(
+            keymap.bind(Action::Sort, &[parse_keys("q").unwrap()]),
+            Ok(())
+        );
+        assert_eq!(keymap.lookup(&[b'q' as i32]), Lookup::Action(Action::Sort));
+    }
+
+    #[test]
+    fn rejects_a_sequence_overlapping_a_key() {
+        let mut keymap = Keymap::default();
+        let error = keymap
+            .bind(Action::Top, &[parse_keys("r r").unwrap()])
+            .unwrap_err();
+        assert_eq!(error, "key 'r r' overlaps 'r' of rescan");
+
+        let mut keymap = Keymap::preset(Preset::Vim);
+        let error = keymap
+            .bind(Action::Sort, &[parse_keys("g").unwrap()])
+            .unwrap_err();
+        assert_eq!(error, "key 'g' overlaps 'g g' of top");
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_sequence() {
+        let keymap = Keymap::preset(Preset::Emacs);
+        assert_eq!(keymap.lookup(&[0x18]), Lookup::Pending);
+        assert_eq!(keymap.lookup(&[0x18, 0x03]), Lookup::Action(Action::Quit));
+        assert_eq!(keymap.lookup(&[0x18, b'x' as i32]), Lookup::Unbound);
+    }
+}
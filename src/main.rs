@@ -1,53 +1,139 @@
 extern crate ncurses;
+mod args;
+mod cli;
+mod config;
+mod form;
+mod hotspot;
+mod ipconfig;
+mod keymap;
 mod network;
+mod nmcli;
+mod output;
+mod qr;
+mod radio;
+mod state;
+mod theme;
 mod ui;
+mod wireguard;
+mod wps;
 
-use std::env;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser};
+use std::fs;
+use std::path::Path;
+
+use args::Args;
 
-const VERSION: &str = env!("CARGO_PKG_VERSION");
 const NAME: &str = env!("CARGO_PKG_NAME");
 
-fn print_version() {
-    println!("{NAME} v{VERSION}");
+// Decoders often wrap the payload in other text, so take the first WIFI: line
+fn read_qr_file(path: &Path) -> Result<String, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    contents
+        .lines()
+        .find_map(|line| {
+            let start = line.to_ascii_uppercase().find("WIFI:")?;
+            Some(line[start..].trim().to_string())
+        })
+        .ok_or_else(|| format!("{}: no WIFI: payload found", path.display()))
+}
+
+fn exit_usage(message: &str) -> ! {
+    eprintln!("{NAME}: {message}");
+    std::process::exit(cli::EXIT_USAGE);
 }
 
-fn print_help() {
-    println!("{NAME} v{VERSION}");
-    println!("A network management tool using ncurses");
-    println!();
-    println!("USAGE:");
-    println!("    {NAME} [OPTIONS]");
-    println!();
-    println!("OPTIONS:");
-    println!("    -h, --help       Print help information");
-    println!("    -v, --version    Print version information");
+// Unknown options are reported as before clap: the message, the help and
+// exit status 1
+fn parse_args() -> Args {
+    let error = match Args::try_parse() {
+        Ok(args) => return args,
+        Err(error) => error,
+    };
+    if error.kind() != ErrorKind::UnknownArgument {
+        error.exit();
+    }
+    let option = match error.get(ContextKind::InvalidArg) {
+        Some(ContextValue::String(option)) => option.clone(),
+        _ => String::new(),
+    };
+    eprintln!("Unknown option: {option}");
+    let _ = Args::command().print_help();
+    std::process::exit(cli::EXIT_FAILURE);
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let args = parse_args();
 
-    if args.len() > 1 {
-        match args[1].as_str() {
-            "-v" | "--version" => {
-                print_version();
-                return Ok(());
-            }
-            "-h" | "--help" => {
-                print_help();
-                return Ok(());
-            }
-            _ => {
-                eprintln!("Unknown option: {}", args[1]);
-                print_help();
-                std::process::exit(1);
+    // Command line options win over the config file
+    let mut settings = config::load(args.config.as_deref()).unwrap_or_else(|e| exit_usage(&e));
+    if let Some(backend) = args.backend {
+        settings.backend = backend;
+    }
+    if let Some(interface) = &args.interface {
+        settings.interface = Some(interface.clone());
+    }
+    if let Some(sort) = args.sort {
+        settings.sort = sort;
+    }
+    if let Some(theme) = &args.theme {
+        settings.theme = theme.clone();
+    }
+    if args.no_color {
+        settings.theme = String::from("monochrome");
+    }
+    if let Some(interval) = args.refresh_interval {
+        settings.refresh_interval = interval;
+    }
+
+    if settings.theme().is_none() {
+        exit_usage(&settings.unknown_theme());
+    }
+
+    // nmcli is the only backend so far
+    let config::Backend::Nmcli = settings.backend;
+    // Only an interface asked for on the command line must exist; one from
+    // the config file may be unplugged, and the first device is used instead
+    let completions = matches!(args.command, Some(args::Command::Completions { .. }));
+    if let (Some(interface), false) = (settings.interface.clone(), completions) {
+        if !nmcli::wifi_devices().contains(&interface) {
+            if args.interface.is_some() {
+                exit_usage(&format!("{interface} is not a Wi-Fi device"));
             }
+            settings.interface = None;
         }
     }
 
-    let mut ui = network::NetworkUi::new();
+    // Subcommands never touch the terminal UI
+    if let Some(command) = &args.command {
+        if args.join_qr.is_some() || args.join_qr_file.is_some() {
+            exit_usage("--join-qr and --join-qr-file only apply to the interface");
+        }
+        std::process::exit(cli::run(command, &settings));
+    }
+
+    let payload = match (&args.join_qr, &args.join_qr_file) {
+        (Some(payload), _) => Some(Ok(payload.clone())),
+        (None, Some(path)) => Some(read_qr_file(path)),
+        (None, None) => None,
+    };
+    let join = match payload.map(|p| p.and_then(|payload| qr::parse_wifi_payload(&payload))) {
+        Some(Ok(credentials)) => Some(credentials),
+        Some(Err(e)) => {
+            eprintln!("{NAME}: {e}");
+            std::process::exit(cli::EXIT_FAILURE);
+        }
+        None => None,
+    };
+
+    let mut ui = network::NetworkUi::new(&settings);
 
     // Initial scan with error handling
     ui.scan();
+    if let Some(credentials) = join {
+        ui.join(&credentials);
+        let _ = ui.run_scan();
+    }
     ui.display_networks();
 
     loop {
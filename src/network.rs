@@ -1,24 +1,54 @@
 use ncurses::ll::werase;
-use ncurses::{COLOR_PAIR, *};
+use ncurses::*;
 use std::sync::mpsc;
 
-use super::ui::Ui;
-use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio};
+use super::config::Settings;
+use super::form::{self, Form};
+use super::ipconfig::{IpConfig, IpFamily, IPV4_METHODS, IPV6_METHODS};
+use super::keymap::{Action, Lookup, ALT};
+use super::nmcli::{self, ProfileSettings, ProfileStatus, WifiProfile};
+use super::qr::WifiCredentials;
+use super::radio::{self, RadioState};
+use super::state::{self, Event, Favorites, HistoryEntry};
+use super::theme::{self, Role};
+use super::ui::{self, Ui};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fmt;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::vec::Vec;
 use zeroize::Zeroizing;
 
-// Constants for UI and signal thresholds
-const STRONG_SIGNAL_THRESHOLD: u8 = 66;
-const MEDIUM_SIGNAL_THRESHOLD: u8 = 33;
-const ENTER_KEY: i32 = 13;
-const ESCAPE_KEY: i32 = 27;
-const UP_ARROW: i32 = 65;
-const DOWN_ARROW: i32 = 66;
-const BACKSPACE_KEY: i32 = 127;
-const BACKSPACE_KEY_ALT: i32 = 8;
+mod connections;
+mod devices;
+mod history;
+mod hotspot;
+mod search;
+mod share;
+mod wireguard;
+mod wps;
+
+// Constants for UI
+pub(crate) const ENTER_KEY: i32 = 13;
+pub(crate) const ESCAPE_KEY: i32 = 27;
+pub(crate) const BACKSPACE_KEY: i32 = 127;
+pub(crate) const BACKSPACE_KEY_ALT: i32 = 8;
+pub(crate) const TAB_KEY: i32 = 9;
+const STATS_REFRESH_MS: i32 = 1000;
+// How long ncurses waits for the rest of a key sequence after Escape
+const ESCAPE_DELAY_MS: i32 = 25;
+
+// Values accepted by 802-11-wireless.cloned-mac-address besides an explicit MAC
+const MAC_ADDRESS_MODES: [&str; 6] = [
+    "default",
+    "permanent",
+    "preserve",
+    "random",
+    "stable",
+    "custom",
+];
 
 // Error types for better error handling
 #[derive(Debug)]
@@ -29,6 +59,17 @@ pub enum NetworkError {
     InvalidInput,
 }
 
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::CommandFailed(e) if e.is_empty() => write!(f, "nmcli command failed"),
+            NetworkError::CommandFailed(e) => write!(f, "{e}"),
+            NetworkError::NoNetworks => write!(f, "No networks found"),
+            NetworkError::InvalidInput => write!(f, "Invalid input"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Network {
     pub in_use: bool,
@@ -36,6 +77,11 @@ pub struct Network {
     pub bssid: String,
     pub security: String,
     pub signal: u8,
+    pub channel: u32,
+    /// Centre frequency in MHz.
+    pub frequency: u32,
+    /// D-Bus object path of the access point, used to query WPS support.
+    pub dbus_path: String,
 }
 
 impl Network {
@@ -46,6 +92,9 @@ impl Network {
             bssid: String::new(),
             security: String::new(),
             signal: 0,
+            channel: 0,
+            frequency: 0,
+            dbus_path: String::new(),
         }
     }
 
@@ -61,75 +110,485 @@ impl Default for Network {
     }
 }
 
+// Actions listed in the Wi-Fi footer, with their labels
+const WIFI_FOOTER: [(Action, &str); 15] = [
+    (Action::NextView, "View"),
+    (Action::Rescan, "Rescan"),
+    (Action::Radio, "Radio"),
+    (Action::NextDevice, "Device"),
+    (Action::Hotspot, "Hotspot"),
+    (Action::Share, "Share"),
+    (Action::Wps, "WPS"),
+    (Action::Disconnect, "Disconnect"),
+    (Action::Forget, "Forget"),
+    (Action::Edit, "Edit"),
+    (Action::IpSettings, "IP"),
+    (Action::Sort, "Sort"),
+    (Action::Favorite, "Favorite"),
+    (Action::Connect, "Connect"),
+    (Action::Quit, "Quit"),
+];
+
+/// Order of the Wi-Fi list. Ties keep the strongest signal first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortMode {
+    /// Strongest signal first
+    #[default]
+    Signal,
+    /// SSID, case-insensitive
+    Ssid,
+    /// Security type, open networks first
+    Security,
+    /// Channel number
+    Channel,
+    /// Networks with a saved profile first
+    SavedFirst,
+    /// The network in use first
+    ConnectedFirst,
+}
+
+impl SortMode {
+    const ALL: [SortMode; 6] = [
+        SortMode::Signal,
+        SortMode::Ssid,
+        SortMode::Security,
+        SortMode::Channel,
+        SortMode::SavedFirst,
+        SortMode::ConnectedFirst,
+    ];
+
+    /// Sorts networks already ordered by signal, as `nmcli::scan` returns them.
+    /// `saved` holds the SSIDs with a profile, needed by `SavedFirst` only.
+    pub fn apply(self, networks: &mut [Network], saved: &HashSet<String>) {
+        match self {
+            SortMode::Signal => {}
+            SortMode::Ssid => networks.sort_by_key(|n| n.ssid.to_lowercase()),
+            SortMode::Security => networks.sort_by(|a, b| a.security.cmp(&b.security)),
+            SortMode::Channel => networks.sort_by_key(|n| n.channel),
+            SortMode::SavedFirst => networks.sort_by_key(|n| !saved.contains(&n.ssid)),
+            SortMode::ConnectedFirst => networks.sort_by_key(|n| !n.in_use),
+        }
+    }
+
+    pub fn needs_profiles(self) -> bool {
+        self == SortMode::SavedFirst
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SortMode::Signal => "signal",
+            SortMode::Ssid => "ssid",
+            SortMode::Security => "security",
+            SortMode::Channel => "channel",
+            SortMode::SavedFirst => "saved first",
+            SortMode::ConnectedFirst => "connected first",
+        }
+    }
+
+    fn next(self) -> SortMode {
+        let i = SortMode::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        SortMode::ALL[(i + 1) % SortMode::ALL.len()]
+    }
+}
+
+/// Networks left out of the Wi-Fi list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filters {
+    /// Hide networks that do not broadcast an SSID.
+    pub hidden: bool,
+    /// Hide networks without security.
+    pub open: bool,
+    /// Hide networks with a signal below `weak_below`.
+    pub weak: bool,
+    /// Show only networks with a saved profile.
+    pub saved_only: bool,
+    pub weak_below: u8,
+}
+
+impl Filters {
+    fn keeps(&self, network: &Network, saved: &HashSet<String>) -> bool {
+        !(self.hidden && network.ssid.is_empty()
+            || self.open && network.security.is_empty()
+            || self.weak && network.signal < self.weak_below
+            || self.saved_only && !saved.contains(&network.ssid))
+    }
+
+    // Short names of the active filters for the header
+    fn describe(&self) -> Vec<String> {
+        let mut active = Vec::new();
+        if self.hidden {
+            active.push(String::from("no hidden"));
+        }
+        if self.open {
+            active.push(String::from("no open"));
+        }
+        if self.weak {
+            active.push(format!("signal {}+", self.weak_below));
+        }
+        if self.saved_only {
+            active.push(String::from("saved only"));
+        }
+        active
+    }
+}
+
+/// Columns of the Wi-Fi list, after the markers for the network in use and
+/// favorites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    Ssid,
+    Security,
+    Bssid,
+    Channel,
+    /// Centre frequency in MHz.
+    Frequency,
+    /// Signal strength as set by `signal.show`.
+    Signal,
+}
+
+/// Parts of the signal column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalPart {
+    /// Up to four bars, following the signal colour thresholds.
+    Bars,
+    Percent,
+    /// Approximate level in dBm, worked back from the percentage.
+    Dbm,
+}
+
+/// Glyphs of the signal bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bars {
+    /// Block glyphs when the locale is UTF-8, ASCII otherwise.
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+/// The tabs of the main window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Wifi,
+    Connections,
+    Devices,
+    WireGuard,
+    History,
+}
+
+impl View {
+    const ALL: [View; 5] = [
+        View::Wifi,
+        View::Connections,
+        View::Devices,
+        View::WireGuard,
+        View::History,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            View::Wifi => "Wi-Fi",
+            View::Connections => "Connections",
+            View::Devices => "Devices",
+            View::WireGuard => "WireGuard",
+            View::History => "History",
+        }
+    }
+
+    fn next(self) -> View {
+        let pos = View::ALL.iter().position(|v| *v == self).unwrap_or(0);
+        View::ALL[(pos + 1) % View::ALL.len()]
+    }
+}
+
 pub struct NetworkUi {
+    view: View,
     highlight: usize,
+    // First row shown of each list. It only moves when the highlight would
+    // leave the screen, so a click never scrolls the list under the pointer.
+    scroll: Cell<usize>,
+    // Networks of the last scan by signal, and the filtered and sorted list
+    // shown from them
+    scanned: Vec<Network>,
     networks: Vec<Network>,
+    // SSIDs with a saved profile, read when a sort or filter needs them
+    saved: Option<HashSet<String>>,
+    // Query of the last `/` search, kept for n and N
+    search: String,
+    favorites: Favorites,
+    // Wi-Fi interfaces and the index of the one used for scanning and connecting
+    devices: Vec<String>,
+    device: usize,
+    radio: RadioState,
+    // Device the nmcurse hotspot is running on, if any
+    hotspot_device: Option<String>,
+    connections: Vec<nmcli::Connection>,
+    connection_highlight: usize,
+    connection_scroll: Cell<usize>,
+    // Every network device for the Devices tab, unlike `devices` which only
+    // holds Wi-Fi interface names
+    device_list: Vec<nmcli::Device>,
+    device_highlight: usize,
+    device_scroll: Cell<usize>,
+    tunnels: Vec<wireguard::Tunnel>,
+    tunnel_highlight: usize,
+    tunnel_scroll: Cell<usize>,
+    // Connects, disconnects and forgets, newest first
+    history: Vec<HistoryEntry>,
+    history_highlight: usize,
+    history_scroll: Cell<usize>,
+    settings: Settings,
+    // Keys typed so far of a multi-key binding such as `g g`
+    pending_keys: Vec<i32>,
     ui: Ui,
 }
 
 impl NetworkUi {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
+        let devices = nmcli::wifi_devices();
+        let device = settings
+            .interface
+            .as_ref()
+            .and_then(|name| devices.iter().position(|d| d == name))
+            .unwrap_or(0);
+        let ui = Ui::new(&settings.theme().unwrap_or_default());
+        if settings.mouse {
+            // Not BUTTON1_PRESSED: it would arrive ahead of a double click
+            let events =
+                BUTTON1_CLICKED | BUTTON1_DOUBLE_CLICKED | BUTTON4_PRESSED | BUTTON5_PRESSED;
+            mousemask(events as mmask_t, None);
+        }
+        let favorites = Favorites::load().unwrap_or_else(|e| {
+            form::show_message("Favorites", &e);
+            Favorites::default()
+        });
+        let mut settings = settings.clone();
+        // Without distinct colours the signal needs a column of its own
+        if !theme::signal_in_color() && !settings.columns.contains(&Column::Signal) {
+            let after_ssid = settings
+                .columns
+                .iter()
+                .position(|c| *c == Column::Ssid)
+                .map_or(0, |i| i + 1);
+            settings.columns.insert(after_ssid, Column::Signal);
+        }
         NetworkUi {
+            view: View::Wifi,
             highlight: 0,
+            scroll: Cell::new(0),
+            scanned: Vec::new(),
             networks: Vec::new(),
-            ui: Ui::new(),
+            saved: None,
+            search: String::new(),
+            favorites,
+            devices,
+            device,
+            radio: RadioState::read(),
+            hotspot_device: None,
+            connections: Vec::new(),
+            connection_highlight: 0,
+            connection_scroll: Cell::new(0),
+            device_list: Vec::new(),
+            device_highlight: 0,
+            device_scroll: Cell::new(0),
+            tunnels: Vec::new(),
+            tunnel_highlight: 0,
+            tunnel_scroll: Cell::new(0),
+            history: Vec::new(),
+            history_highlight: 0,
+            history_scroll: Cell::new(0),
+            settings,
+            pending_keys: Vec::new(),
+            ui,
         }
     }
 
+    fn current_device(&self) -> Option<&str> {
+        self.devices.get(self.device).map(String::as_str)
+    }
+
+    fn next_device(&mut self) {
+        // Pick up dongles plugged in since the last switch
+        let current = self.current_device().map(str::to_string);
+        self.devices = nmcli::wifi_devices();
+        let position = current
+            .and_then(|current| self.devices.iter().position(|d| *d == current))
+            .unwrap_or(0);
+        self.device = if self.devices.is_empty() {
+            0
+        } else {
+            (position + 1) % self.devices.len()
+        };
+    }
+
     pub fn display_networks(&mut self) {
         unsafe { werase(self.ui.win()) };
+        match self.view {
+            View::Connections => return self.display_connections(),
+            View::Devices => return self.display_devices(),
+            View::WireGuard => return self.display_tunnels(),
+            View::History => return self.display_history(),
+            View::Wifi => {}
+        }
+
         if self.networks.is_empty() {
+            self.draw_header_and_footer();
+            self.draw_empty_message();
             wrefresh(self.ui.win());
             return;
         }
 
-        let (_max_ssid_length, max_security_length) = self.calculate_max_lengths();
-
-        // Calculate the maximum length of SSID and security strings to fit the window
-        // -6 for padding and formatting
-        let max_combined_length: usize =
-            std::cmp::max(0, getmaxx(self.ui.win()) - max_security_length as i32 - 6) as usize;
+        let widths = self.column_widths();
         // Calculate the window height, leaving space for the header and footer
         let win_height: usize = std::cmp::max(0, getmaxy(self.ui.win()) - 4) as usize;
 
-        let (start_index, end_index) = self.calculate_display_range(win_height);
+        let (start_index, end_index) = calculate_display_range(
+            self.highlight,
+            self.networks.len(),
+            win_height,
+            &self.scroll,
+        );
 
         self.draw_header_and_footer();
 
-        self.render_networks(
-            start_index,
-            end_index,
-            max_combined_length,
-            max_security_length,
-        );
+        self.render_networks(start_index, end_index, &widths);
 
         wrefresh(self.ui.win());
     }
 
-    fn calculate_max_lengths(&self) -> (usize, usize) {
-        let mut max_ssid_length = 3;
-        let mut max_security_length = 3;
-        for network in &self.networks {
-            if network.ssid.len() > max_ssid_length {
-                max_ssid_length = network.ssid.len();
-            }
-            if network.security.len() > max_security_length {
-                max_security_length = network.security.len();
-            }
+    // Width of every configured column; the SSID takes whatever the others
+    // leave of the window
+    fn column_widths(&self) -> Vec<usize> {
+        let columns = &self.settings.columns;
+        let mut widths: Vec<usize> = columns
+            .iter()
+            .map(|column| {
+                self.networks
+                    .iter()
+                    .map(|n| self.column_text(n, *column).chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(3)
+            })
+            .collect();
+
+        if let Some(ssid) = columns.iter().position(|c| *c == Column::Ssid) {
+            // 5 for the border and the markers, 2 between columns
+            let others: usize = widths
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != ssid)
+                .map(|(_, w)| w + 2)
+                .sum();
+            widths[ssid] = (getmaxx(self.ui.win()).max(0) as usize)
+                .saturating_sub(others + 5)
+                .max(3);
         }
-        (max_ssid_length, max_security_length)
+        widths
     }
 
-    fn calculate_display_range(&self, win_height: usize) -> (usize, usize) {
-        let start_index: usize =
-            std::cmp::max(0, self.highlight as i32 - win_height as i32 + 1) as usize;
-        let end_index: usize = std::cmp::min(self.networks.len(), start_index + win_height);
-        (start_index, end_index)
+    fn column_text(&self, network: &Network, column: Column) -> String {
+        match column {
+            Column::Ssid => network.ssid.clone(),
+            Column::Security => self.format_security(&network.security),
+            Column::Bssid => network.bssid.clone(),
+            Column::Channel => network.channel.to_string(),
+            Column::Frequency => format!("{} MHz", network.frequency),
+            Column::Signal => self.signal_text(network.signal),
+        }
+    }
+
+    fn signal_text(&self, signal: u8) -> String {
+        let parts: Vec<String> = self
+            .settings
+            .signal_parts
+            .iter()
+            .map(|part| match part {
+                SignalPart::Bars => self.signal_bars(signal),
+                SignalPart::Percent => format!("{signal:>3}%"),
+                // NetworkManager maps -100..-40 dBm linearly onto 0..100%
+                SignalPart::Dbm => format!("{:>4} dBm", signal as i32 * 3 / 5 - 100),
+            })
+            .collect();
+        parts.join(" ")
+    }
+
+    // One bar below the medium threshold, two up to the strong one, three
+    // above it and four once halfway from there to 100
+    fn signal_bars(&self, signal: u8) -> String {
+        let strong = self.settings.strong_signal;
+        let bars = if signal >= strong + (100 - strong) / 2 {
+            4
+        } else if signal >= strong {
+            3
+        } else if signal >= self.settings.medium_signal {
+            2
+        } else {
+            1
+        };
+        if self.settings.ascii_bars {
+            format!("{:.<4}", "#".repeat(bars))
+        } else {
+            let glyphs: String = "▂▄▆█".chars().take(bars).collect();
+            format!("{glyphs:<4}")
+        }
     }
 
     fn draw_header_and_footer(&self) {
-        wattron(self.ui.win(), COLOR_PAIR(5));
-        let _ = mvwprintw(self.ui.win(), 1, 3, "Available Networks");
+        let header = match self.current_device() {
+            Some(device) if self.devices.len() > 1 => format!(
+                "Available Networks on {device} ({}/{})",
+                self.device + 1,
+                self.devices.len()
+            ),
+            Some(device) => format!("Available Networks on {device}"),
+            None => String::from("Available Networks"),
+        };
+        let mut view = vec![format!("sort: {}", self.settings.sort.name())];
+        view.extend(self.settings.filters.describe());
+        if !self.search.is_empty() {
+            view.push(format!("/{}", self.search));
+        }
+        let header = format!("{header}  [{}]", view.join(", "));
+        let status = match &self.hotspot_device {
+            Some(device) => format!("Hotspot: on ({device})  {}", self.radio.describe()),
+            None => self.radio.describe(),
+        };
+        self.draw_frame(&header, &status, &self.settings.keymap.footer(&WIFI_FOOTER));
+    }
+
+    // Draws the tab bar, the view title with a right aligned status, and the footer
+    fn draw_frame(&self, title: &str, status: &str, footer: &str) {
+        let mut x = 2;
+        for view in View::ALL {
+            let label = format!(" {} ", view.title());
+            let attr = if view == self.view {
+                theme::style(Role::Header) | A_BOLD() | A_REVERSE()
+            } else {
+                theme::style(Role::Header)
+            };
+            wattron(self.ui.win(), attr);
+            let _ = mvwaddstr(self.ui.win(), 0, x, &label);
+            wattroff(self.ui.win(), attr);
+            x += label.chars().count() as i32 + 1;
+        }
+
+        // Not mvwprintw, the title holds the search query and device name,
+        // the footer any key the user bound, all of which may contain %
+        wattron(self.ui.win(), theme::style(Role::Header));
+        let _ = mvwaddstr(self.ui.win(), 1, 3, title);
+
+        let status_x = getmaxx(self.ui.win()) - status.chars().count() as i32 - 3;
+        if !status.is_empty() && status_x > title.chars().count() as i32 + 4 {
+            let _ = mvwaddstr(self.ui.win(), 1, status_x, status);
+        }
+        wattroff(self.ui.win(), theme::style(Role::Header));
+
+        wattron(self.ui.win(), theme::style(Role::Border));
         mvwhline(self.ui.win(), 2, 1, 0, getmaxx(self.ui.win()) - 2);
         mvwhline(
             self.ui.win(),
@@ -138,44 +597,59 @@ impl NetworkUi {
             0,
             getmaxx(self.ui.win()) - 2,
         );
+        wattroff(self.ui.win(), theme::style(Role::Border));
 
         // Display the footer
-        let _ = mvwprintw(
-            self.ui.win(),
-            getmaxy(self.ui.win()) - 1,
-            3,
-            "[r: Rescan, d: Disconnect, f: Forget, enter: Connect, q: Quit]",
-        );
+        wattron(self.ui.win(), theme::style(Role::Footer));
+        let _ = mvwaddstr(self.ui.win(), getmaxy(self.ui.win()) - 1, 3, footer);
+        wattroff(self.ui.win(), theme::style(Role::Footer));
+    }
+
+    // Explains why the list is empty instead of leaving a blank window
+    fn draw_empty_message(&self) {
+        let key = |action| self.settings.keymap.key_for(action).unwrap_or_default();
+        let message = if self.radio.hard_blocked {
+            String::from("Wi-Fi is blocked by a hardware switch.")
+        } else if self.radio.enabled && self.radio.soft_blocked {
+            String::from("Wi-Fi is soft blocked (airplane mode). Try `rfkill unblock wifi`.")
+        } else if !self.radio.is_usable() {
+            format!(
+                "Wi-Fi radio is disabled. Press {} to turn it on.",
+                key(Action::Radio)
+            )
+        } else if self.devices.is_empty() {
+            String::from("No Wi-Fi device found.")
+        } else if !self.scanned.is_empty() {
+            format!(
+                "All {} networks found are hidden by filters.",
+                self.scanned.len()
+            )
+        } else {
+            format!(
+                "No networks found. Press {} to rescan.",
+                key(Action::Rescan)
+            )
+        };
 
-        wattroff(self.ui.win(), COLOR_PAIR(5));
+        wattron(self.ui.win(), theme::style(Role::Text));
+        let _ = mvwaddstr(self.ui.win(), 4, 3, &message);
+        wattroff(self.ui.win(), theme::style(Role::Text));
     }
 
-    fn render_networks(
-        &self,
-        start_index: usize,
-        end_index: usize,
-        max_combined_length: usize,
-        max_security_length: usize,
-    ) {
+    fn render_networks(&self, start_index: usize, end_index: usize, widths: &[usize]) {
         for i in start_index..end_index {
             let color = self.get_signal_color(self.networks[i].signal);
-            self.render_single_network(
-                i,
-                start_index,
-                color,
-                max_combined_length,
-                max_security_length,
-            );
+            self.render_single_network(i, start_index, color, widths);
         }
     }
 
     fn get_signal_color(&self, signal: u8) -> u32 {
-        if signal >= STRONG_SIGNAL_THRESHOLD {
-            COLOR_PAIR(3)
-        } else if signal >= MEDIUM_SIGNAL_THRESHOLD {
-            COLOR_PAIR(2)
+        if signal >= self.settings.strong_signal {
+            theme::style(Role::SignalStrong)
+        } else if signal >= self.settings.medium_signal {
+            theme::style(Role::SignalMedium)
         } else {
-            COLOR_PAIR(1)
+            theme::style(Role::SignalWeak)
         }
     }
 
@@ -184,37 +658,86 @@ impl NetworkUi {
         i: usize,
         start_index: usize,
         mut color: u32,
-        max_combined_length: usize,
-        max_security_length: usize,
+        widths: &[usize],
     ) {
+        let network = &self.networks[i];
         let mut ss = String::new();
-        if self.networks[i].in_use {
+        if network.in_use {
             color |= ncurses::A_BOLD();
-            ss.push_str("> ");
+            ss.push('>');
         } else {
-            ss.push_str("  ");
+            ss.push(' ');
         }
+        ss.push_str(if self.favorites.contains(&network.ssid) {
+            "* "
+        } else {
+            "  "
+        });
 
-        let ssid = self.format_ssid(&self.networks[i].ssid, max_combined_length);
-        let security = self.format_security(&self.networks[i].security);
-
-        ss.push_str(&format!(
-            "{:<width_ssid$}{:<width_security$}  ",
-            ssid,
-            security,
-            width_ssid = max_combined_length,
-            width_security = max_security_length,
-        ));
+        let cells: Vec<String> = self
+            .settings
+            .columns
+            .iter()
+            .zip(widths)
+            .map(|(column, width)| {
+                let text = match column {
+                    Column::Ssid => self.format_ssid(&network.ssid, *width),
+                    _ => self.column_text(network, *column),
+                };
+                format!("{text:<width$}")
+            })
+            .collect();
+        ss.push_str(&cells.join("  "));
 
         let display_color = if i == self.highlight {
-            color | ncurses::A_REVERSE()
+            theme::selected(color)
         } else {
             color
         };
 
+        let y = (i - start_index + 3) as i32;
         wattron(self.ui.win(), display_color);
-        let _ = mvwprintw(self.ui.win(), (i - start_index + 3) as i32, 1, &ss);
+        // Not mvwprintw, SSIDs and percentages may contain %
+        let _ = mvwaddstr(self.ui.win(), y, 1, &ss);
         wattroff(self.ui.win(), display_color);
+
+        // Draw the characters matching the search over the row
+        let Some(ssid_column) = self
+            .settings
+            .columns
+            .iter()
+            .position(|c| *c == Column::Ssid)
+        else {
+            return;
+        };
+        let Some(positions) =
+            search::match_positions(&network.ssid, &self.search, self.settings.search_fuzzy)
+        else {
+            return;
+        };
+        // 1 for the border, 3 for the markers, 2 between columns
+        let x = 4 + widths[..ssid_column].iter().map(|w| w + 2).sum::<usize>();
+        // Past a cut, the ellipsis stands in for the rest
+        let visible = self.format_ssid(&network.ssid, widths[ssid_column]);
+        let shown = if visible == network.ssid {
+            visible.chars().count()
+        } else {
+            visible.chars().count().saturating_sub(3)
+        };
+        let match_color = if i == self.highlight {
+            theme::selected(theme::style(Role::Search))
+        } else {
+            theme::style(Role::Search)
+        };
+        wattron(self.ui.win(), match_color);
+        let chars: Vec<char> = network.ssid.chars().collect();
+        for position in positions.into_iter().take_while(|p| *p < shown) {
+            // Wide characters before the match take two cells each
+            let before: String = chars[..position].iter().collect();
+            let cell = x + ui::display_width(&before);
+            let _ = mvwaddstr(self.ui.win(), y, cell as i32, &chars[position].to_string());
+        }
+        wattroff(self.ui.win(), match_color);
     }
 
     fn format_ssid(&self, ssid: &str, max_length: usize) -> String {
@@ -224,8 +747,10 @@ impl NetworkUi {
             ssid.to_string()
         };
 
-        if result.len() > max_length {
-            result.truncate(max_length - 3);
+        // Counted in characters, so a cut never lands inside one
+        let max_length = max_length.max(3);
+        if result.chars().count() > max_length {
+            result = result.chars().take(max_length - 3).collect();
             result.push_str("...");
         }
         result
@@ -240,49 +765,13 @@ impl NetworkUi {
     }
 
     pub fn run_scan(&mut self) -> Result<(), NetworkError> {
+        self.scanned.clear();
         self.networks.clear();
-        let output = Command::new("nmcli")
-            .args(&[
-                "-f",
-                "IN-USE,SSID,BSSID,SECURITY,SIGNAL",
-                "--mode",
-                "multiline",
-                "--terse",
-                "dev",
-                "wifi",
-                "list",
-            ])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn();
-
-        let mut child = match output {
-            Ok(child) => child,
-            Err(e) => return Err(NetworkError::CommandFailed(e.to_string())),
-        };
-
-        let reader = BufReader::new(child.stdout.take().unwrap());
-
-        let mut network = Network::new();
-
-        for line in reader.lines().filter_map(|l| l.ok()) {
-            if line.starts_with("IN-USE:") {
-                network.in_use = line[7..].trim() == "*";
-            } else if line.starts_with("SSID:") {
-                network.ssid = line[5..].trim().to_string();
-            } else if line.starts_with("BSSID:") {
-                network.bssid = line[6..].trim().to_string();
-            } else if line.starts_with("SECURITY:") {
-                network.security = line[9..].trim().to_string();
-            } else if line.starts_with("SIGNAL:") {
-                network.signal = line[7..].trim().parse::<u8>().unwrap_or(0);
-                self.networks.push(network.clone());
-                network = Network::new();
-            }
-        }
-
-        self.networks.sort_by(|a, b| b.signal.cmp(&a.signal));
-        let _ = child.wait();
+        self.saved = None;
+        self.radio = RadioState::read();
+        self.hotspot_device = crate::hotspot::active_device();
+        self.scanned = nmcli::scan(self.current_device())?;
+        self.update_list();
 
         if self.networks.is_empty() {
             Err(NetworkError::NoNetworks)
@@ -291,6 +780,37 @@ impl NetworkUi {
         }
     }
 
+    // Applies the filters and the sort order to the last scan
+    fn update_list(&mut self) {
+        let filters = self.settings.filters;
+        let sort = self.settings.sort;
+        if self.saved.is_none() && (filters.saved_only || sort.needs_profiles()) {
+            self.saved = Some(nmcli::saved_ssids());
+        }
+        let saved = self.saved.clone().unwrap_or_default();
+        let narrow = self.settings.search_narrow && !self.search.is_empty();
+        self.networks = self
+            .scanned
+            .iter()
+            .filter(|n| filters.keeps(n, &saved) && (!narrow || self.matches_search(&n.ssid)))
+            .cloned()
+            .collect();
+        sort.apply(&mut self.networks, &saved);
+        // Favorites stay on top, in the chosen order among themselves
+        let favorites = &self.favorites;
+        self.networks.sort_by_key(|n| !favorites.contains(&n.ssid));
+    }
+
+    // Rebuilds the list after a sort or filter change, keeping the same
+    // access point highlighted when it is still shown
+    fn relist(&mut self) {
+        let selected = self.networks.get(self.highlight).map(|n| n.bssid.clone());
+        self.update_list();
+        self.highlight = selected
+            .and_then(|bssid| self.networks.iter().position(|n| n.bssid == bssid))
+            .unwrap_or(0);
+    }
+
     pub fn scan(&mut self) {
         self.ui.clear();
         let (tx, rx) = mpsc::channel();
@@ -313,108 +833,486 @@ impl NetworkUi {
     }
 
     fn get_input(&self) -> i32 {
-        set_escdelay(0);
-        let mut input = wgetch(self.ui.win());
-        if input == ERR {
-            return ERR;
-        }
+        // Long enough for a key sequence split over a slow link to be decoded
+        // whole, short enough that Escape still feels immediate
+        set_escdelay(ESCAPE_DELAY_MS);
+        // The WireGuard tab polls so its traffic counters stay live, the
+        // Wi-Fi tab when automatic rescans are on
+        let timeout = match self.view {
+            View::WireGuard => STATS_REFRESH_MS,
+            View::Wifi if self.settings.refresh_interval > 0 => {
+                i32::try_from(self.settings.refresh_interval.saturating_mul(1000))
+                    .unwrap_or(i32::MAX)
+            }
+            _ => -1,
+        };
+        wtimeout(self.ui.win(), timeout);
+        loop {
+            // Known escape sequences come back as KEY_* codes from keypad mode
+            let input = wgetch(self.ui.win());
+            if input != ESCAPE_KEY {
+                return input;
+            }
 
-        // Fix for alt/escape/arrows (also f-keys on some terminals)
-        if input == ESCAPE_KEY {
+            // Whatever follows a lone Escape straight away was typed with Alt,
+            // unless more follows: the rest of a sequence terminfo lacks
             nodelay(self.ui.win(), true);
-            input = wgetch(self.ui.win());
+            let next = wgetch(self.ui.win());
+            let mut unknown_sequence = false;
+            if next == '[' as i32 || next == 'O' as i32 {
+                while wgetch(self.ui.win()) != ERR {
+                    unknown_sequence = true;
+                }
+            }
             nodelay(self.ui.win(), false);
 
+            match next {
+                ERR => return ESCAPE_KEY,
+                _ if unknown_sequence => continue,
+                _ => return ALT | next,
+            }
+        }
+    }
+
+    pub fn select_network(&mut self) -> Option<usize> {
+        loop {
+            let input = self.get_input();
             if input == ERR {
-                return ESCAPE_KEY;
+                match self.view {
+                    View::WireGuard => self.refresh_tunnel_stats(),
+                    View::Wifi if self.settings.refresh_interval > 0 => self.auto_rescan(),
+                    _ => return None,
+                }
+                self.display_networks();
+                continue;
             }
 
-            if input != 91 {
-                return input;
+            let action = if input == KEY_MOUSE {
+                self.mouse_action()
+            } else {
+                self.read_action(input)
+            };
+            let Some(action) = action else {
+                // A click may have moved the highlight
+                if input == KEY_MOUSE {
+                    self.display_networks();
+                }
+                continue;
+            };
+            match action {
+                Action::Quit => return None,
+                Action::NextView => {
+                    self.view = self.view.next();
+                    match self.view {
+                        View::Connections => self.refresh_connections(),
+                        View::Devices => self.refresh_devices(),
+                        View::WireGuard => self.refresh_tunnels(),
+                        View::History => self.refresh_history(),
+                        View::Wifi => {}
+                    }
+                }
+                _ if self.view == View::Connections => self.handle_connections_action(action),
+                _ if self.view == View::Devices => self.handle_devices_action(action),
+                _ if self.view == View::WireGuard => self.handle_tunnels_action(action),
+                _ if self.view == View::History => {
+                    if let Some(index) = self.handle_history_action(action) {
+                        return Some(index);
+                    }
+                }
+                // Enter on an empty list has nothing to connect to, keep waiting
+                // for a rescan or a radio toggle instead of leaving
+                Action::Connect if self.highlight < self.networks.len() => {
+                    return Some(self.highlight);
+                }
+                _ => self.handle_wifi_action(action),
             }
 
-            // Arrow keys
-            nodelay(self.ui.win(), true);
-            input = wgetch(self.ui.win());
-            nodelay(self.ui.win(), false);
+            self.display_networks();
+        }
+    }
 
-            if input != ERR {
-                return input;
+    // Adds a key to the sequence typed so far. Returns the action once the
+    // sequence is complete; a key that ends no sequence starts over on its own.
+    fn read_action(&mut self, input: i32) -> Option<Action> {
+        self.pending_keys.push(input);
+        let mut lookup = self.settings.keymap.lookup(&self.pending_keys);
+        if lookup == Lookup::Unbound && self.pending_keys.len() > 1 {
+            self.pending_keys = vec![input];
+            lookup = self.settings.keymap.lookup(&self.pending_keys);
+        }
+        match lookup {
+            Lookup::Pending => None,
+            Lookup::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            }
+            Lookup::Unbound => {
+                self.pending_keys.clear();
+                None
             }
-
-            return ESCAPE_KEY;
         }
-        input
     }
 
-    pub fn select_network(&mut self) -> Option<usize> {
-        if self.networks.is_empty() {
+    // Turns a mouse event into an action. A click highlights the row under
+    // it, a double click also connects and the wheel moves up and down.
+    fn mouse_action(&mut self) -> Option<Action> {
+        let mut event = MEVENT {
+            id: 0,
+            x: 0,
+            y: 0,
+            z: 0,
+            bstate: 0,
+        };
+        if getmouse(&mut event) != OK {
+            return None;
+        }
+        let pressed = |mask: i32| event.bstate & mask as mmask_t != 0;
+        if pressed(BUTTON4_PRESSED) {
+            return Some(Action::Up);
+        }
+        if pressed(BUTTON5_PRESSED) {
+            return Some(Action::Down);
+        }
+        if !pressed(BUTTON1_CLICKED | BUTTON1_DOUBLE_CLICKED) {
+            return None;
+        }
+
+        let (highlight, scroll, len, rows) = self.current_list();
+        let (start, end) = calculate_display_range(*highlight, len, rows, scroll);
+        // Rows start below the tab bar, the title and its rule
+        let row = start + usize::try_from(event.y - 3).ok()?;
+        if row >= end {
             return None;
         }
+        *highlight = row;
+        pressed(BUTTON1_DOUBLE_CLICKED).then_some(Action::Connect)
+    }
 
-        let mut input = self.get_input();
+    // Highlighted row, scroll offset, length and visible rows of the current
+    // view's list
+    fn current_list(&mut self) -> (&mut usize, &Cell<usize>, usize, usize) {
+        let rows = std::cmp::max(0, getmaxy(self.ui.win()) - 4) as usize;
+        match self.view {
+            View::Wifi => (&mut self.highlight, &self.scroll, self.networks.len(), rows),
+            View::Connections => (
+                &mut self.connection_highlight,
+                &self.connection_scroll,
+                self.connections.len(),
+                rows,
+            ),
+            View::Devices => (
+                &mut self.device_highlight,
+                &self.device_scroll,
+                self.device_list.len(),
+                rows.saturating_sub(devices::DETAIL_LINES as usize + 1),
+            ),
+            View::WireGuard => (
+                &mut self.tunnel_highlight,
+                &self.tunnel_scroll,
+                self.tunnels.len(),
+                rows,
+            ),
+            View::History => (
+                &mut self.history_highlight,
+                &self.history_scroll,
+                self.history.len(),
+                rows,
+            ),
+        }
+    }
 
-        while input != ERR && input != ENTER_KEY && input != 'q' as i32 && input != ESCAPE_KEY {
-            match input {
-                _ if input == 'r' as i32 => {
-                    self.scan();
-                    self.highlight = 0;
-                }
-                UP_ARROW if self.highlight > 0 => {
-                    self.highlight -= 1;
-                }
-                DOWN_ARROW if self.highlight < self.networks.len() - 1 => {
-                    self.highlight += 1;
+    // Rows available to a list below the title and above the footer
+    fn page_size(&self) -> usize {
+        std::cmp::max(1, getmaxy(self.ui.win()) - 4) as usize
+    }
+
+    // Rescans without the loading screen, keeping the same access point
+    // highlighted
+    fn auto_rescan(&mut self) {
+        let selected = self.networks.get(self.highlight).map(|n| n.bssid.clone());
+        let _ = self.run_scan();
+        self.highlight = selected
+            .and_then(|bssid| self.networks.iter().position(|n| n.bssid == bssid))
+            .unwrap_or(0);
+    }
+
+    fn handle_wifi_action(&mut self, action: Action) {
+        let page = self.page_size();
+        let selected = self.highlight < self.networks.len();
+        match action {
+            Action::Rescan => {
+                self.scan();
+                self.highlight = 0;
+            }
+            _ if navigate(&mut self.highlight, self.networks.len(), page, action) => {}
+            Action::Details if selected => self.show_details(&self.networks[self.highlight]),
+            Action::Search => self.search(),
+            Action::SearchNext => self.jump_to_match(true, false),
+            Action::SearchPrevious => self.jump_to_match(false, false),
+            Action::Sort => {
+                self.settings.sort = self.settings.sort.next();
+                self.relist();
+            }
+            Action::FilterHidden => {
+                self.settings.filters.hidden = !self.settings.filters.hidden;
+                self.relist();
+            }
+            Action::FilterOpen => {
+                self.settings.filters.open = !self.settings.filters.open;
+                self.relist();
+            }
+            Action::FilterWeak => {
+                self.settings.filters.weak = !self.settings.filters.weak;
+                self.relist();
+            }
+            Action::FilterSaved => {
+                self.settings.filters.saved_only = !self.settings.filters.saved_only;
+                self.relist();
+            }
+            Action::Favorite if selected && !self.networks[self.highlight].ssid.is_empty() => {
+                let ssid = self.networks[self.highlight].ssid.clone();
+                if let Err(e) = self.favorites.toggle(&ssid) {
+                    form::show_message("Favorites", &e);
                 }
-                _ if input == 'd' as i32 => {
-                    if self.highlight < self.networks.len() && self.networks[self.highlight].in_use
-                    {
-                        self.disconnect(&self.networks[self.highlight].ssid);
-                        let _ = self.run_scan();
-                    }
+                self.relist();
+            }
+            Action::Disconnect if selected && self.networks[self.highlight].in_use => {
+                self.disconnect(&self.networks[self.highlight]);
+                let _ = self.run_scan();
+            }
+            Action::Forget if selected => {
+                self.forget_password(&self.networks[self.highlight]);
+                let _ = self.run_scan();
+            }
+            Action::Radio => {
+                self.toggle_radio();
+                self.highlight = 0;
+            }
+            Action::Hotspot => {
+                self.hotspot_dialog();
+                self.highlight = 0;
+            }
+            Action::Wps if selected && !self.networks[self.highlight].in_use => {
+                self.ui.clear();
+                self.wps_connect(self.highlight);
+                let _ = self.run_scan();
+            }
+            Action::Share if selected => {
+                self.share_network(&self.networks[self.highlight].ssid);
+            }
+            Action::NextDevice if self.devices.len() > 1 => {
+                self.next_device();
+                self.scan();
+                self.highlight = 0;
+            }
+            Action::Edit if selected => {
+                if let Some(profile) =
+                    self.editable_profile(&self.networks[self.highlight].ssid, "Edit profile")
+                {
+                    self.edit_profile(&profile.uuid, &profile.name);
                 }
-                _ if input == 'f' as i32 => {
-                    if self.highlight < self.networks.len()
-                        && self.is_password_cached(&self.networks[self.highlight].ssid)
-                    {
-                        self.forget_password(&self.networks[self.highlight].ssid);
-                        let _ = self.run_scan();
-                    }
+            }
+            Action::IpSettings if selected => {
+                if let Some(profile) =
+                    self.editable_profile(&self.networks[self.highlight].ssid, "IP settings")
+                {
+                    self.edit_ip_settings(&profile.uuid, &profile.name);
                 }
-                _ => {} // Ignore unknown input
             }
+            _ => {} // Ignore actions that do not apply
+        }
+    }
 
-            self.display_networks();
-            input = self.get_input();
+    fn toggle_radio(&mut self) {
+        if self.radio.hard_blocked {
+            form::show_message(
+                "Wi-Fi radio",
+                "Wi-Fi is blocked by a hardware switch and cannot be enabled here.",
+            );
+            return;
         }
 
-        if input == ERR || input == 'q' as i32 || input == ESCAPE_KEY {
-            return None;
+        let enable = !self.radio.is_usable();
+        let message = if enable {
+            "Turning Wi-Fi on..."
+        } else {
+            "Turning Wi-Fi off..."
+        };
+
+        let result = self.run_task(message.to_string(), move || radio::set_wifi_enabled(enable));
+        if let Err(e) = result {
+            form::show_message("Wi-Fi radio", &e.to_string());
         }
 
-        if self.highlight < self.networks.len() {
-            Some(self.highlight)
+        if enable {
+            self.scan();
+        } else {
+            let _ = self.run_scan();
+        }
+    }
+
+    fn show_details(&self, network: &Network) {
+        let profile = match nmcli::profile_status(&network.ssid) {
+            ProfileStatus::NoProfile => String::from("none"),
+            ProfileStatus::SavedProfile(p) => format!("{} (no password)", p.name),
+            ProfileStatus::SavedSecret(p) => p.name,
+        };
+        let ssid = if network.ssid.is_empty() {
+            "(hidden)"
+        } else {
+            network.ssid.as_str()
+        };
+        form::show_message(
+            "Network details",
+            &format!(
+                "SSID:      {ssid}\nBSSID:     {}\nSecurity:  {}\nSignal:    {}/100\nChannel:   {} ({} MHz)\nIn use:    {}\nProfile:   {profile}",
+                network.bssid,
+                self.format_security(&network.security),
+                network.signal,
+                network.channel,
+                network.frequency,
+                if network.in_use { "yes" } else { "no" },
+            ),
+        );
+    }
+
+    // Picks the profile the editors work on, telling the user when there is none
+    fn editable_profile(&self, ssid: &str, title: &str) -> Option<WifiProfile> {
+        let profile = nmcli::find_profiles(ssid).into_iter().next();
+        if profile.is_none() {
+            form::show_message(title, &format!("No saved profile for {ssid}"));
+        }
+        profile
+    }
+
+    fn edit_profile(&self, uuid: &str, name: &str) {
+        let settings = match ProfileSettings::load(uuid) {
+            Ok(settings) => settings,
+            Err(e) => {
+                form::show_message("Edit profile", &e.to_string());
+                return;
+            }
+        };
+
+        let mac_mode = match settings.cloned_mac_address.as_str() {
+            "" => "default",
+            mode if MAC_ADDRESS_MODES.contains(&mode) => mode,
+            _ => "custom",
+        };
+        let custom_mac = if mac_mode == "custom" {
+            settings.cloned_mac_address.as_str()
         } else {
-            None
+            ""
+        };
+
+        let mut form = Form::new(&format!("Profile: {name}"))
+            .choice(
+                "Autoconnect",
+                &["yes", "no"],
+                if settings.autoconnect { "yes" } else { "no" },
+            )
+            .text("Priority", &settings.autoconnect_priority.to_string())
+            .choice("Metered", &["unknown", "yes", "no"], &settings.metered)
+            .choice("MAC address", &MAC_ADDRESS_MODES, mac_mode)
+            .text("Custom MAC", custom_mac)
+            .text("Lock to BSSID", &settings.bssid);
+
+        let submitted = form.run(|form| {
+            if form.value("Priority").trim().parse::<i32>().is_err() {
+                return Err(String::from("Priority must be an integer"));
+            }
+            let custom_mac = form.value("Custom MAC").trim();
+            if form.value("MAC address") == "custom" && !nmcli::is_mac_address(custom_mac) {
+                return Err(String::from("Custom MAC must look like 12:34:56:78:9A:BC"));
+            }
+            let bssid = form.value("Lock to BSSID").trim();
+            if !bssid.is_empty() && !nmcli::is_mac_address(bssid) {
+                return Err(String::from("BSSID must look like 12:34:56:78:9A:BC"));
+            }
+            Ok(())
+        });
+        if !submitted {
+            return;
+        }
+
+        let settings = ProfileSettings {
+            autoconnect: form.value("Autoconnect") == "yes",
+            autoconnect_priority: form.value("Priority").trim().parse().unwrap_or(0),
+            metered: form.value("Metered").to_string(),
+            cloned_mac_address: match form.value("MAC address") {
+                "default" => String::new(),
+                "custom" => form.value("Custom MAC").trim().to_string(),
+                mode => mode.to_string(),
+            },
+            bssid: form.value("Lock to BSSID").trim().to_string(),
+        };
+
+        if let Err(e) = settings.apply(uuid) {
+            form::show_message("Edit profile", &e.to_string());
         }
     }
 
-    fn is_password_cached(&self, network: &str) -> bool {
-        Command::new("nmcli")
-            .args(&[
-                "-t",
-                "-f",
-                "802-11-wireless-security.psk",
-                "connection",
-                "show",
-                network,
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .and_then(|child| child.wait_with_output())
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+    fn edit_ip_settings(&self, uuid: &str, name: &str) {
+        let configs = IpConfig::load(uuid, IpFamily::V4)
+            .and_then(|v4| IpConfig::load(uuid, IpFamily::V6).map(|v6| (v4, v6)));
+        let (ipv4, ipv6) = match configs {
+            Ok(configs) => configs,
+            Err(e) => {
+                form::show_message("IP settings", &e.to_string());
+                return;
+            }
+        };
+
+        let mut form = Form::new(&format!("IP settings: {name}"))
+            .choice("IPv4 method", &IPV4_METHODS, &ipv4.method)
+            .text("IPv4 addresses", &ipv4.addresses)
+            .text("IPv4 gateway", &ipv4.gateway)
+            .text("IPv4 DNS", &ipv4.dns)
+            .text("IPv4 search", &ipv4.dns_search)
+            .text("IPv4 routes", &ipv4.routes)
+            .choice("IPv6 method", &IPV6_METHODS, &ipv6.method)
+            .text("IPv6 addresses", &ipv6.addresses)
+            .text("IPv6 gateway", &ipv6.gateway)
+            .text("IPv6 DNS", &ipv6.dns)
+            .text("IPv6 search", &ipv6.dns_search)
+            .text("IPv6 routes", &ipv6.routes);
+
+        let read_form = |form: &Form| {
+            (
+                IpConfig {
+                    family: IpFamily::V4,
+                    method: form.value("IPv4 method").to_string(),
+                    addresses: form.value("IPv4 addresses").to_string(),
+                    gateway: form.value("IPv4 gateway").to_string(),
+                    dns: form.value("IPv4 DNS").to_string(),
+                    dns_search: form.value("IPv4 search").to_string(),
+                    routes: form.value("IPv4 routes").to_string(),
+                },
+                IpConfig {
+                    family: IpFamily::V6,
+                    method: form.value("IPv6 method").to_string(),
+                    addresses: form.value("IPv6 addresses").to_string(),
+                    gateway: form.value("IPv6 gateway").to_string(),
+                    dns: form.value("IPv6 DNS").to_string(),
+                    dns_search: form.value("IPv6 search").to_string(),
+                    routes: form.value("IPv6 routes").to_string(),
+                },
+            )
+        };
+
+        let submitted = form.run(|form| {
+            let (ipv4, ipv6) = read_form(form);
+            ipv4.validate()?;
+            ipv6.validate()
+        });
+        if !submitted {
+            return;
+        }
+
+        let (ipv4, ipv6) = read_form(&form);
+        if let Err(e) = IpConfig::apply(uuid, &[ipv4, ipv6]) {
+            form::show_message("IP settings", &e.to_string());
+        }
     }
 
     fn get_password(&self) -> Zeroizing<String> {
@@ -466,69 +1364,148 @@ impl NetworkUi {
         }
 
         self.ui.clear();
-        let mut cmd = Command::new("nmcli");
-
-        if self.is_password_cached(&self.networks[index].ssid) {
-            cmd.args(&["con", "up", "id", self.networks[index].ssid.as_str()]);
-        } else {
-            // Create a new window for password input
-            let pass_win = newwin(3, COLS(), LINES() / 2 - 1, 0);
-            unsafe {
-                werase(pass_win);
+        let network = &self.networks[index];
+        let status = nmcli::profile_status(&network.ssid);
+
+        let password = match status {
+            ProfileStatus::SavedSecret(_) => Zeroizing::new(String::new()),
+            ProfileStatus::SavedProfile(_) => {
+                // The profile exists but its secret is missing
+                let password = self.get_password();
+                if password.is_empty() {
+                    return;
+                }
+                password
             }
-            let password = self.get_password();
-            delwin(pass_win);
-
-            cmd.args(&["dev", "wifi", "connect", &self.networks[index].bssid]);
-
-            if !password.is_empty() {
-                cmd.args(&["password", password.as_str()]);
+            ProfileStatus::NoProfile => {
+                // Create a new window for password input
+                let pass_win = newwin(3, COLS(), LINES() / 2 - 1, 0);
+                unsafe {
+                    werase(pass_win);
+                }
+                let password = self.get_password();
+                delwin(pass_win);
+                password
             }
-        }
-        cmd.stderr(Stdio::null()).stdout(Stdio::null());
-        self.ui.clear();
+        };
 
-        self.run_loading_animation(
-            format!("Connecting to {}...", self.networks[index].ssid),
-            move || {
-                let _ = cmd.spawn().and_then(|mut child| child.wait());
-            },
+        self.activate(
+            &status,
+            &network.ssid,
+            Some(&network.bssid),
+            &password,
+            false,
         );
     }
 
-    fn disconnect(&self, network: &str) {
-        if network.is_empty() {
+    /// Joins the network described by a scanned Wi-Fi QR code.
+    pub fn join(&self, credentials: &WifiCredentials) {
+        self.ui.clear();
+        if let Some(eap) = &credentials.eap {
+            let device = self.current_device().map(str::to_string);
+            let ssid = credentials.ssid.clone();
+            let hidden = credentials.hidden;
+            let password = credentials.password.clone();
+            let eap = eap.clone();
+            let result = self.run_task(
+                format!("Connecting to {}...", credentials.ssid),
+                move || nmcli::activate_eap(&ssid, hidden, &eap, &password, device.as_deref()),
+            );
+            state::record(Event::Connect, &credentials.ssid, "", &result);
+            if let Err(e) = result {
+                form::show_message("Join network", &e.to_string());
+            }
             return;
         }
 
-        let mut cmd = Command::new("nmcli");
-        cmd.args(&["con", "down", "id", network])
-            .stderr(Stdio::null())
-            .stdout(Stdio::null());
-        self.ui.clear();
+        // A code always carries the current secret, so refresh it on any
+        // saved profile instead of trusting what is stored
+        let status = match nmcli::profile_status(&credentials.ssid) {
+            ProfileStatus::SavedSecret(profile) if !credentials.password.is_empty() => {
+                ProfileStatus::SavedProfile(profile)
+            }
+            status => status,
+        };
+        // A code names no access point, so any one of the network will do
+        self.activate(
+            &status,
+            &credentials.ssid,
+            None,
+            &credentials.password,
+            credentials.hidden,
+        );
+    }
+
+    // Brings up a saved profile, handing it `password` when given, or creates
+    // a new one for the access point `bssid`, any of `ssid` without one
+    fn activate(
+        &self,
+        status: &ProfileStatus,
+        ssid: &str,
+        bssid: Option<&str>,
+        password: &str,
+        hidden: bool,
+    ) {
+        let status = status.clone();
+        let target = bssid.unwrap_or(ssid).to_string();
+        let password = Zeroizing::new(password.to_string());
+        let device = self.current_device().map(str::to_string);
 
-        self.run_loading_animation(format!("Disconnecting from {}...", network), move || {
-            let _ = cmd.spawn().and_then(|mut child| child.wait());
+        let result = self.run_task(format!("Connecting to {}...", ssid), move || {
+            nmcli::activate(&status, &target, &password, hidden, device.as_deref())
         });
+        state::record(Event::Connect, ssid, bssid.unwrap_or_default(), &result);
+        if let Err(e) = result {
+            form::show_message("Connect", &format!("Could not connect to {ssid}:\n{e}"));
+        }
     }
 
-    fn forget_password(&self, network: &str) {
-        if network.is_empty() {
+    fn disconnect(&self, network: &Network) {
+        if network.ssid.is_empty() {
             return;
         }
 
-        let mut cmd = Command::new("nmcli");
-        cmd.args(&["connection", "delete", network])
-            .stderr(Stdio::null())
-            .stdout(Stdio::null());
-        self.ui.clear();
+        let ssid = network.ssid.clone();
+        let result = self.run_task(
+            format!("Disconnecting from {}...", network.ssid),
+            move || nmcli::disconnect(&ssid),
+        );
+        state::record(Event::Disconnect, &network.ssid, &network.bssid, &result);
+        if let Err(e) = result {
+            form::show_message("Disconnect", &e.to_string());
+        }
+    }
 
-        self.run_loading_animation(
-            format!("Forgetting password for {}...", network),
-            move || {
-                let _ = cmd.spawn().and_then(|mut child| child.wait());
-            },
+    fn forget_password(&self, network: &Network) {
+        let ssid = network.ssid.clone();
+        let result = self.run_task(
+            format!("Forgetting password for {}...", network.ssid),
+            move || nmcli::forget(&ssid),
         );
+        // Nothing was deleted when there was no profile, so nothing to record
+        if !matches!(result, Ok(0)) {
+            let outcome = result.as_ref().map(|_| ());
+            state::record(Event::Forget, &network.ssid, &network.bssid, &outcome);
+        }
+        if let Err(e) = result {
+            form::show_message("Forget", &e.to_string());
+        }
+    }
+
+    // Like run_loading_animation, but hands the operation's result back
+    fn run_task<T, F>(&self, message: String, operation: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let result = Arc::new(Mutex::new(None));
+        let result_clone = Arc::clone(&result);
+        self.ui.clear();
+        self.run_loading_animation(message, move || {
+            *result_clone.lock().unwrap() = Some(operation());
+        });
+        let value = result.lock().unwrap().take();
+        value.expect("operation finished without a result")
     }
 
     fn run_loading_animation<F>(&self, message: String, operation: F)
@@ -562,8 +1539,43 @@ impl NetworkUi {
     }
 }
 
+// Moves a list highlight for the navigation actions. Returns false for any
+// other action so callers can handle it.
+fn navigate(highlight: &mut usize, len: usize, page: usize, action: Action) -> bool {
+    let last = len.saturating_sub(1);
+    *highlight = match action {
+        Action::Up => highlight.saturating_sub(1),
+        Action::Down => (*highlight + 1).min(last),
+        Action::PageUp => highlight.saturating_sub(page),
+        Action::PageDown => (*highlight + page).min(last),
+        Action::Top => 0,
+        Action::Bottom => last,
+        _ => return false,
+    };
+    true
+}
+
+// Returns the visible slice of a list, scrolling `scroll` only as far as
+// needed to keep the highlighted row on screen
+fn calculate_display_range(
+    highlight: usize,
+    len: usize,
+    win_height: usize,
+    scroll: &Cell<usize>,
+) -> (usize, usize) {
+    let mut start_index = scroll.get().min(len.saturating_sub(win_height));
+    if highlight < start_index {
+        start_index = highlight;
+    } else if highlight >= start_index + win_height {
+        start_index = highlight + 1 - win_height.max(1);
+    }
+    scroll.set(start_index);
+    let end_index: usize = std::cmp::min(len, start_index + win_height);
+    (start_index, end_index)
+}
+
 impl Default for NetworkUi {
     fn default() -> Self {
-        Self::new()
+        Self::new(&Settings::default())
     }
 }
@@ -0,0 +1,167 @@
+use ncurses::*;
+
+use super::{calculate_display_range, navigate, NetworkUi};
+use crate::form;
+use crate::keymap::Action;
+use crate::nmcli::{self, Connection};
+use crate::theme::{self, Role};
+
+impl NetworkUi {
+    /// Reloads the saved connection profiles shown in the Connections tab.
+    pub(super) fn refresh_connections(&mut self) {
+        match nmcli::connections() {
+            Ok(connections) => {
+                self.connections = connections
+                    .into_iter()
+                    .filter(|c| c.kind != "loopback")
+                    .collect();
+            }
+            Err(e) => {
+                self.connections.clear();
+                form::show_message("Connections", &e.to_string());
+            }
+        }
+        if self.connection_highlight >= self.connections.len() {
+            self.connection_highlight = self.connections.len().saturating_sub(1);
+        }
+    }
+
+    pub(super) fn display_connections(&self) {
+        let active = self.connections.iter().filter(|c| c.active).count();
+        self.draw_frame(
+            "Connections",
+            &format!("{active} active"),
+            &self.settings.keymap.footer(&[
+                (Action::NextView, "View"),
+                (Action::Rescan, "Refresh"),
+                (Action::Connect, "Up/Down"),
+                (Action::Edit, "Edit"),
+                (Action::IpSettings, "IP"),
+                (Action::Quit, "Quit"),
+            ]),
+        );
+
+        if self.connections.is_empty() {
+            wattron(self.ui.win(), theme::style(Role::Text));
+            let _ = mvwprintw(self.ui.win(), 4, 3, "No saved connections.");
+            wattroff(self.ui.win(), theme::style(Role::Text));
+            wrefresh(self.ui.win());
+            return;
+        }
+
+        let labels: Vec<String> = self
+            .connections
+            .iter()
+            .map(Connection::type_label)
+            .collect();
+        let type_width = labels.iter().map(|l| l.len()).max().unwrap_or(0);
+        let device_width = self
+            .connections
+            .iter()
+            .map(|c| c.device.len())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+        let name_width = std::cmp::max(
+            3,
+            getmaxx(self.ui.win()) - type_width as i32 - device_width as i32 - 10,
+        ) as usize;
+        let win_height = std::cmp::max(0, getmaxy(self.ui.win()) - 4) as usize;
+        let (start_index, end_index) = calculate_display_range(
+            self.connection_highlight,
+            self.connections.len(),
+            win_height,
+            &self.connection_scroll,
+        );
+
+        let rows = self.connections.iter().zip(&labels).enumerate();
+        for (i, (connection, label)) in rows.take(end_index).skip(start_index) {
+            let marker = if connection.active { "> " } else { "  " };
+            let device = if connection.device.is_empty() {
+                "---"
+            } else {
+                connection.device.as_str()
+            };
+            let line = format!(
+                "{marker}{:<name_width$}  {:<type_width$}  {:<device_width$}",
+                self.format_ssid(&connection.name, name_width),
+                label,
+                device,
+            );
+
+            let mut attr = if connection.active {
+                theme::style(Role::Connected)
+            } else {
+                theme::style(Role::Text)
+            };
+            if i == self.connection_highlight {
+                attr = theme::selected(attr);
+            }
+            wattron(self.ui.win(), attr);
+            // Not mvwprintw, profiles are often named after their SSID
+            let _ = mvwaddstr(self.ui.win(), (i - start_index + 3) as i32, 1, &line);
+            wattroff(self.ui.win(), attr);
+        }
+
+        wrefresh(self.ui.win());
+    }
+
+    pub(super) fn handle_connections_action(&mut self, action: Action) {
+        let page = self.page_size();
+        let selected = self.connections.get(self.connection_highlight).cloned();
+        match (action, selected) {
+            (Action::Rescan, _) => self.refresh_connections(),
+            (_, _)
+                if navigate(
+                    &mut self.connection_highlight,
+                    self.connections.len(),
+                    page,
+                    action,
+                ) => {}
+            (Action::Connect, Some(connection)) => {
+                self.toggle_connection(&connection);
+                self.refresh_connections();
+            }
+            (Action::Disconnect, Some(connection)) if connection.active => {
+                self.toggle_connection(&connection);
+                self.refresh_connections();
+            }
+            (Action::Edit, Some(connection)) => {
+                if connection.kind == "802-11-wireless" {
+                    self.edit_profile(&connection.uuid, &connection.name);
+                } else {
+                    let key = self.settings.keymap.key_for(Action::IpSettings);
+                    form::show_message(
+                        "Edit profile",
+                        &format!(
+                            "Only Wi-Fi profiles have these settings. Use {} for IP settings.",
+                            key.unwrap_or_default()
+                        ),
+                    );
+                }
+            }
+            (Action::IpSettings, Some(connection)) => {
+                self.edit_ip_settings(&connection.uuid, &connection.name);
+            }
+            _ => {} // Ignore actions that do not apply
+        }
+    }
+
+    // Brings an inactive profile up and an active one down
+    pub(super) fn toggle_connection(&self, connection: &Connection) {
+        let uuid = connection.uuid.clone();
+        let result = if connection.active {
+            self.run_task(format!("Deactivating {}...", connection.name), move || {
+                nmcli::connection_down(&uuid)
+            })
+        } else {
+            self.run_task(format!("Activating {}...", connection.name), move || {
+                nmcli::connection_up(&uuid, None)
+            })
+        };
+
+        if let Err(e) = result {
+            form::show_message(&connection.name, &e.to_string());
+        }
+    }
+}
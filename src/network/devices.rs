@@ -0,0 +1,165 @@
+use ncurses::*;
+
+use super::{calculate_display_range, navigate, NetworkUi};
+use crate::form;
+use crate::keymap::Action;
+use crate::nmcli::{self, Device};
+use crate::theme::{self, Role};
+
+// Lines reserved under the list for details of the highlighted device
+pub(super) const DETAIL_LINES: i32 = 4;
+
+impl NetworkUi {
+    /// Reloads the device list shown in the Devices tab.
+    pub(super) fn refresh_devices(&mut self) {
+        match nmcli::devices() {
+            Ok(devices) => self.device_list = devices,
+            Err(e) => {
+                self.device_list.clear();
+                form::show_message("Devices", &e.to_string());
+            }
+        }
+        if self.device_highlight >= self.device_list.len() {
+            self.device_highlight = self.device_list.len().saturating_sub(1);
+        }
+    }
+
+    pub(super) fn display_devices(&self) {
+        let connected = self.device_list.iter().filter(|d| d.is_connected()).count();
+        self.draw_frame(
+            "Devices",
+            &format!("{connected} connected"),
+            &self.settings.keymap.footer(&[
+                (Action::NextView, "View"),
+                (Action::Rescan, "Refresh"),
+                (Action::Connect, "Connect/Disconnect"),
+                (Action::Disconnect, "Disconnect"),
+                (Action::Quit, "Quit"),
+            ]),
+        );
+
+        if self.device_list.is_empty() {
+            wattron(self.ui.win(), theme::style(Role::Text));
+            let _ = mvwprintw(self.ui.win(), 4, 3, "No network devices found.");
+            wattroff(self.ui.win(), theme::style(Role::Text));
+            wrefresh(self.ui.win());
+            return;
+        }
+
+        let width = |f: fn(&Device) -> usize| self.device_list.iter().map(f).max().unwrap_or(0);
+        let name_width = width(|d| d.name.len());
+        let type_width = width(|d| d.kind.len());
+        let state_width = width(|d| d.state.len());
+
+        let win_height = std::cmp::max(0, getmaxy(self.ui.win()) - 4 - DETAIL_LINES - 1) as usize;
+        let (start_index, end_index) = calculate_display_range(
+            self.device_highlight,
+            self.device_list.len(),
+            win_height,
+            &self.device_scroll,
+        );
+
+        let devices = self.device_list.iter().enumerate();
+        for (i, device) in devices.take(end_index).skip(start_index) {
+            let connection = if device.connection.is_empty() {
+                "---"
+            } else {
+                device.connection.as_str()
+            };
+            let line = format!(
+                "  {:<name_width$}  {:<type_width$}  {:<state_width$}  {}",
+                device.name, device.kind, device.state, connection,
+            );
+
+            let mut attr = match device.state.as_str() {
+                s if s.starts_with("connected") => theme::style(Role::Connected),
+                "unavailable" | "unmanaged" => theme::style(Role::Error),
+                _ => theme::style(Role::Text),
+            };
+            if i == self.device_highlight {
+                attr = theme::selected(attr);
+            }
+            wattron(self.ui.win(), attr);
+            // Not mvwprintw, connection names may contain %
+            let _ = mvwaddstr(self.ui.win(), (i - start_index + 3) as i32, 1, &line);
+            wattroff(self.ui.win(), attr);
+        }
+
+        if let Some(device) = self.device_list.get(self.device_highlight) {
+            self.draw_device_details(device);
+        }
+
+        wrefresh(self.ui.win());
+    }
+
+    fn draw_device_details(&self, device: &Device) {
+        let top = getmaxy(self.ui.win()) - 1 - DETAIL_LINES;
+        let or_dashes = |value: &str| {
+            if value.is_empty() {
+                String::from("---")
+            } else {
+                value.to_string()
+            }
+        };
+
+        wattron(self.ui.win(), theme::style(Role::Border));
+        mvwhline(self.ui.win(), top - 1, 1, 0, getmaxx(self.ui.win()) - 2);
+        wattroff(self.ui.win(), theme::style(Role::Border));
+
+        let lines = [
+            format!(
+                "Device: {}  Driver: {}",
+                device.name,
+                or_dashes(&device.driver)
+            ),
+            format!("MAC:    {}", or_dashes(&device.hwaddr)),
+            format!("IPv4:   {}", or_dashes(&device.ipv4.join(", "))),
+            format!("IPv6:   {}", or_dashes(&device.ipv6.join(", "))),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            let _ = mvwaddstr(self.ui.win(), top + i as i32, 3, line);
+        }
+    }
+
+    pub(super) fn handle_devices_action(&mut self, action: Action) {
+        let page = self.page_size();
+        let selected = self.device_list.get(self.device_highlight).cloned();
+        match (action, selected) {
+            (Action::Rescan, _) => self.refresh_devices(),
+            (_, _)
+                if navigate(
+                    &mut self.device_highlight,
+                    self.device_list.len(),
+                    page,
+                    action,
+                ) => {}
+            (Action::Connect, Some(device)) => {
+                self.toggle_device(&device);
+                self.refresh_devices();
+            }
+            (Action::Disconnect, Some(device)) if device.is_connected() => {
+                self.toggle_device(&device);
+                self.refresh_devices();
+            }
+            _ => {} // Ignore actions that do not apply
+        }
+    }
+
+    // Connects a disconnected device and disconnects a connected one
+    fn toggle_device(&self, device: &Device) {
+        let name = device.name.clone();
+        let result = if device.is_connected() {
+            self.run_task(format!("Disconnecting {}...", device.name), move || {
+                nmcli::device_disconnect(&name)
+            })
+        } else {
+            self.run_task(format!("Connecting {}...", device.name), move || {
+                nmcli::device_connect(&name)
+            })
+        };
+
+        if let Err(e) = result {
+            form::show_message(&device.name, &e.to_string());
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use ncurses::*;
+
+use super::{calculate_display_range, navigate, NetworkUi, View};
+use crate::form;
+use crate::keymap::Action;
+use crate::state;
+use crate::theme::{self, Role};
+
+impl NetworkUi {
+    /// Reloads the history shown in the History tab, newest first.
+    pub(super) fn refresh_history(&mut self) {
+        match state::history() {
+            Ok(entries) => self.history = entries.into_iter().rev().collect(),
+            Err(e) => {
+                self.history.clear();
+                form::show_message("History", &e);
+            }
+        }
+        if self.history_highlight >= self.history.len() {
+            self.history_highlight = self.history.len().saturating_sub(1);
+        }
+    }
+
+    pub(super) fn display_history(&self) {
+        let failed = self.history.iter().filter(|e| !e.ok).count();
+        self.draw_frame(
+            "History (UTC)",
+            &format!("{} entries, {failed} failed", self.history.len()),
+            &self.settings.keymap.footer(&[
+                (Action::NextView, "View"),
+                (Action::Rescan, "Refresh"),
+                (Action::Connect, "Reconnect"),
+                (Action::Quit, "Quit"),
+            ]),
+        );
+
+        if self.history.is_empty() {
+            wattron(self.ui.win(), theme::style(Role::Text));
+            let _ = mvwprintw(self.ui.win(), 4, 3, "Nothing has happened yet.");
+            wattroff(self.ui.win(), theme::style(Role::Text));
+            wrefresh(self.ui.win());
+            return;
+        }
+
+        let ssid_width = self
+            .history
+            .iter()
+            .map(|e| e.ssid.chars().count())
+            .max()
+            .unwrap_or(0)
+            .clamp(3, 32);
+        let line_width = std::cmp::max(0, getmaxx(self.ui.win()) - 3) as usize;
+        let win_height = std::cmp::max(0, getmaxy(self.ui.win()) - 4) as usize;
+        let (start_index, end_index) = calculate_display_range(
+            self.history_highlight,
+            self.history.len(),
+            win_height,
+            &self.history_scroll,
+        );
+
+        let entries = self.history.iter().enumerate();
+        for (i, entry) in entries.take(end_index).skip(start_index) {
+            let bssid = if entry.bssid.is_empty() {
+                "---"
+            } else {
+                entry.bssid.as_str()
+            };
+            let outcome = match &entry.error {
+                _ if entry.ok => String::from("ok"),
+                Some(error) => format!("failed: {}", error.replace('\n', " ")),
+                None => String::from("failed"),
+            };
+            let line = format!(
+                "  {}  {:<10}  {:<ssid_width$}  {bssid:<17}  {outcome}",
+                entry.time(),
+                entry.event.name(),
+                self.format_ssid(&entry.ssid, ssid_width),
+            );
+            let line: String = line.chars().take(line_width).collect();
+
+            let mut attr = if entry.ok {
+                theme::style(Role::Text)
+            } else {
+                theme::style(Role::Error)
+            };
+            if i == self.history_highlight {
+                attr = theme::selected(attr);
+            }
+            wattron(self.ui.win(), attr);
+            // Not mvwprintw, SSIDs and error messages may contain %
+            let _ = mvwaddstr(self.ui.win(), (i - start_index + 3) as i32, 1, &line);
+            wattroff(self.ui.win(), attr);
+        }
+
+        wrefresh(self.ui.win());
+    }
+
+    /// Handles a key in the History tab. Returns the row of the Wi-Fi list to
+    /// connect to when an entry is reconnected.
+    pub(super) fn handle_history_action(&mut self, action: Action) -> Option<usize> {
+        let page = self.page_size();
+        match action {
+            Action::Rescan => self.refresh_history(),
+            _ if navigate(
+                &mut self.history_highlight,
+                self.history.len(),
+                page,
+                action,
+            ) => {}
+            Action::Connect => return self.reconnect(),
+            _ => {} // Ignore actions that do not apply
+        }
+        None
+    }
+
+    // Finds the highlighted entry's network in the Wi-Fi list, the same
+    // access point when it is still there, and switches to it
+    fn reconnect(&mut self) -> Option<usize> {
+        let entry = self.history.get(self.history_highlight)?;
+        if entry.ssid.is_empty() {
+            return None;
+        }
+        let index = self
+            .networks
+            .iter()
+            .position(|n| !entry.bssid.is_empty() && n.bssid == entry.bssid)
+            .or_else(|| self.networks.iter().position(|n| n.ssid == entry.ssid));
+        let Some(index) = index else {
+            form::show_message(
+                "Reconnect",
+                &format!(
+                    "{} is not in the Wi-Fi list. Rescan or change the filters to reconnect.",
+                    entry.ssid
+                ),
+            );
+            return None;
+        };
+
+        self.view = View::Wifi;
+        self.highlight = index;
+        if self.networks[index].in_use {
+            form::show_message(
+                "Reconnect",
+                &format!("Already connected to {}.", entry.ssid),
+            );
+            return None;
+        }
+        Some(index)
+    }
+}
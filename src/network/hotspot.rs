@@ -0,0 +1,98 @@
+use zeroize::Zeroizing;
+
+use super::NetworkUi;
+use crate::form::{self, Form};
+use crate::hotspot::{self, HotspotConfig, BANDS, SECURITY_MODES};
+use crate::nmcli;
+
+impl NetworkUi {
+    /// Starts a hotspot on the current Wi-Fi device, or shows the running one
+    /// with its clients and offers to stop it.
+    pub(super) fn hotspot_dialog(&mut self) {
+        if let Some(device) = self.hotspot_device.clone() {
+            self.hotspot_status(&device);
+        } else {
+            self.start_hotspot();
+        }
+        let _ = self.run_scan();
+    }
+
+    fn hotspot_status(&self, device: &str) {
+        let clients = hotspot::clients(device);
+        let mut message = format!("Hotspot running on {device}\n\n");
+        if clients.is_empty() {
+            message.push_str("No clients connected.");
+        } else {
+            message.push_str(&format!("{} client(s):\n", clients.len()));
+            for client in &clients {
+                message.push_str(&format!(
+                    "  {}  {}  {}\n",
+                    client.mac, client.ip, client.hostname
+                ));
+            }
+        }
+
+        if !form::confirm(
+            "Hotspot",
+            &format!("{}\n\nStop the hotspot?", message.trim_end()),
+        ) {
+            return;
+        }
+        if let Err(e) = self.run_task(String::from("Stopping hotspot..."), hotspot::stop) {
+            form::show_message("Hotspot", &e.to_string());
+        }
+    }
+
+    fn start_hotspot(&self) {
+        let Some(device) = self.current_device().map(str::to_string) else {
+            form::show_message("Hotspot", "No Wi-Fi device found.");
+            return;
+        };
+
+        let hostname = std::fs::read_to_string("/etc/hostname").unwrap_or_default();
+        let default_ssid = match hostname.trim() {
+            "" => String::from("nmcurse"),
+            name => format!("{name}-hotspot"),
+        };
+
+        let mut form = Form::new(&format!("Hotspot on {device}"))
+            .text("SSID", &default_ssid)
+            .choice("Band", &BANDS, "bg")
+            .text("Channel", "")
+            .choice("Security", &SECURITY_MODES, "wpa2")
+            .secret("Password")
+            .text("Upstream", &hotspot::default_upstream(&device));
+
+        let read_form = |form: &Form| HotspotConfig {
+            ssid: form.value("SSID").to_string(),
+            band: form.value("Band").to_string(),
+            channel: form.value("Channel").trim().to_string(),
+            security: form.value("Security").to_string(),
+            password: Zeroizing::new(form.value("Password").to_string()),
+            upstream: form.value("Upstream").trim().to_string(),
+        };
+
+        let submitted = form.run(|form| {
+            let config = read_form(form);
+            config.validate()?;
+            if !config.upstream.is_empty()
+                && !nmcli::connections()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|c| c.name == config.upstream)
+            {
+                return Err(format!("No connection named {}", config.upstream));
+            }
+            Ok(())
+        });
+        if !submitted {
+            return;
+        }
+
+        let config = read_form(&form);
+        let message = format!("Starting hotspot {}...", config.ssid);
+        if let Err(e) = self.run_task(message, move || hotspot::start(&device, &config)) {
+            form::show_message("Hotspot", &e.to_string());
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use ncurses::*;
+
+use super::{NetworkUi, BACKSPACE_KEY, BACKSPACE_KEY_ALT, ENTER_KEY, ESCAPE_KEY};
+use crate::theme::{self, Role};
+
+/// Positions of the characters of `text` matched by `query`, ignoring case.
+///
+/// Without `fuzzy` the query must appear as one piece; with it the query's
+/// characters only have to appear in order.
+pub(super) fn match_positions(text: &str, query: &str, fuzzy: bool) -> Option<Vec<usize>> {
+    let text: Vec<char> = text.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    let same = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+    if query.is_empty() {
+        return None;
+    }
+
+    if fuzzy {
+        let mut positions = Vec::new();
+        let mut rest = query.iter().peekable();
+        for (i, c) in text.iter().enumerate() {
+            if rest.next_if(|q| same(**q, *c)).is_some() {
+                positions.push(i);
+            }
+        }
+        return rest.peek().is_none().then_some(positions);
+    }
+
+    let start = (0..=text.len().checked_sub(query.len())?)
+        .find(|start| query.iter().zip(&text[*start..]).all(|(q, c)| same(*q, *c)))?;
+    Some((start..start + query.len()).collect())
+}
+
+impl NetworkUi {
+    pub(super) fn matches_search(&self, ssid: &str) -> bool {
+        match_positions(ssid, &self.search, self.settings.search_fuzzy).is_some()
+    }
+
+    // Reads the query a key at a time, moving to the first match from where
+    // the search started. Escape puts back the previous query and row.
+    pub(super) fn search(&mut self) {
+        let origin = self.networks.get(self.highlight).map(|n| n.bssid.clone());
+        let origin_row = self.highlight;
+        let previous = std::mem::take(&mut self.search);
+        wtimeout(self.ui.win(), -1);
+
+        loop {
+            if self.settings.search_narrow {
+                self.relist();
+            } else {
+                self.highlight = origin_row;
+                self.jump_to_match(true, true);
+            }
+            self.display_networks();
+            self.draw_search_prompt();
+
+            // Wide reads, so SSIDs in any script can be searched for
+            let (ch, typed) = match wget_wch(self.ui.win()) {
+                Some(WchResult::KeyCode(code)) => (code, None),
+                Some(WchResult::Char(c)) => (c as i32, char::from_u32(c)),
+                None => continue,
+            };
+            if ch == ENTER_KEY {
+                break;
+            } else if ch == ESCAPE_KEY {
+                self.search = previous;
+                if self.settings.search_narrow {
+                    self.update_list();
+                }
+                self.highlight = origin
+                    .and_then(|bssid| self.networks.iter().position(|n| n.bssid == bssid))
+                    .unwrap_or(0);
+                break;
+            } else if ch == KEY_BACKSPACE || ch == BACKSPACE_KEY || ch == BACKSPACE_KEY_ALT {
+                self.search.pop();
+            } else if let Some(c) = typed.filter(|c| !c.is_control()) {
+                self.search.push(c);
+            }
+        }
+    }
+
+    /// Moves the highlight to the next match after it, or before it when
+    /// `forward` is false, wrapping around the list. `inclusive` also accepts
+    /// the highlighted row itself.
+    pub(super) fn jump_to_match(&mut self, forward: bool, inclusive: bool) {
+        let len = self.networks.len();
+        if self.search.is_empty() || len == 0 {
+            return;
+        }
+        let from = self.highlight.min(len - 1);
+        let skip = if inclusive { 0 } else { 1 };
+        let found = (skip..len + skip)
+            .map(|step| {
+                if forward {
+                    (from + step) % len
+                } else {
+                    (from + len - step % len) % len
+                }
+            })
+            .find(|i| self.matches_search(&self.networks[*i].ssid));
+        if let Some(i) = found {
+            self.highlight = i;
+        }
+    }
+
+    // Replaces the footer with the query and how many networks match
+    fn draw_search_prompt(&self) {
+        let win = self.ui.win();
+        let y = getmaxy(win) - 1;
+        let count = self
+            .networks
+            .iter()
+            .filter(|n| self.matches_search(&n.ssid))
+            .count();
+        let prompt = if self.search.is_empty() {
+            String::from("/")
+        } else {
+            format!(
+                "/{}  [{count} matching, Enter: Done, Esc: Cancel]",
+                self.search
+            )
+        };
+
+        wmove(win, y, 0);
+        wclrtoeol(win);
+        wattron(win, theme::style(Role::Border));
+        mvwhline(win, y, 1, 0, getmaxx(win) - 2);
+        wattroff(win, theme::style(Role::Border));
+        wattron(win, theme::style(Role::Footer));
+        let _ = mvwaddstr(win, y, 3, &prompt);
+        wattroff(win, theme::style(Role::Footer));
+        wrefresh(win);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::match_positions;
+
+    #[test]
+    fn finds_the_query_as_one_piece() {
+        assert_eq!(
+            match_positions("HomeNet", "net", false),
+            Some(vec![4, 5, 6])
+        );
+        assert_eq!(
+            match_positions("HomeNet", "HOME", false),
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq!(match_positions("HomeNet", "hnt", false), None);
+        assert_eq!(match_positions("Net", "Network", false), None);
+    }
+
+    #[test]
+    fn finds_the_query_in_order_when_fuzzy() {
+        assert_eq!(match_positions("HomeNet", "hnt", true), Some(vec![0, 4, 6]));
+        assert_eq!(match_positions("HomeNet", "tn", true), None);
+        assert_eq!(match_positions("aab", "ab", true), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        assert_eq!(
+            match_positions("Café 東京", "東京", false),
+            Some(vec![5, 6])
+        );
+        assert_eq!(match_positions("CAFÉ", "é", false), Some(vec![3]));
+        assert_eq!(match_positions("Ünsere", "ün", true), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn empty_query_matches_nothing() {
+        assert_eq!(match_positions("HomeNet", "", false), None);
+        assert_eq!(match_positions("HomeNet", "", true), None);
+        assert_eq!(match_positions("", "a", false), None);
+    }
+}
@@ -0,0 +1,101 @@
+use ncurses::*;
+use zeroize::Zeroizing;
+
+use super::NetworkUi;
+use crate::form;
+use crate::nmcli;
+use crate::qr;
+use crate::theme::{self, Role};
+
+impl NetworkUi {
+    /// Shows the saved network as a QR code guests can scan to join.
+    ///
+    /// The secret is only read after the user confirms, and the payload and
+    /// rendered code are zeroed once the dialog closes.
+    pub(super) fn share_network(&self, ssid: &str) {
+        let Some(profile) = nmcli::find_profiles(ssid).into_iter().next() else {
+            form::show_message("Share network", &format!("No saved profile for {ssid}"));
+            return;
+        };
+
+        let (security, property) = match profile.key_mgmt.as_str() {
+            "" => ("nopass", None),
+            "none" => ("WEP", Some("802-11-wireless-security.wep-key0")),
+            "wpa-psk" | "sae" => ("WPA", Some("802-11-wireless-security.psk")),
+            other => {
+                form::show_message(
+                    "Share network",
+                    &format!("Networks using {other} cannot be shared as a QR code."),
+                );
+                return;
+            }
+        };
+
+        if property.is_some()
+            && !form::confirm(
+                "Share network",
+                &format!(
+                    "Show the password for {ssid} as a QR code?\nAnyone who can see the screen can scan it."
+                ),
+            )
+        {
+            return;
+        }
+
+        let password = match property {
+            Some(property) => match nmcli::connection_secret(&profile.uuid, property) {
+                Ok(password) if !password.is_empty() => password,
+                Ok(_) => {
+                    form::show_message("Share network", "No password is stored for this network.");
+                    return;
+                }
+                Err(e) => {
+                    form::show_message("Share network", &e.to_string());
+                    return;
+                }
+            },
+            None => Zeroizing::new(String::new()),
+        };
+        let hidden = nmcli::connection_fields(&profile.uuid, &["802-11-wireless.hidden"])
+            .map(|values| values[0] == "yes")
+            .unwrap_or(false);
+
+        let payload = qr::wifi_payload(ssid, security, &password, hidden);
+        drop(password);
+        match qr::render(&payload) {
+            Ok(lines) => self.show_qr(ssid, &lines),
+            Err(e) => form::show_message("Share network", &e),
+        }
+    }
+
+    fn show_qr(&self, ssid: &str, lines: &[String]) {
+        let qr_width = lines.first().map(|l| l.chars().count()).unwrap_or(0) as i32;
+        let width = std::cmp::max(qr_width, ssid.chars().count() as i32 + 4) + 4;
+        let height = lines.len() as i32 + 4;
+        if width > COLS() || height > LINES() {
+            form::show_message(
+                "Share network",
+                &format!("The terminal needs at least {width}x{height} cells to show the QR code."),
+            );
+            return;
+        }
+
+        let win = newwin(height, width, (LINES() - height) / 2, (COLS() - width) / 2);
+        box_(win, 0, 0);
+        wattron(win, theme::style(Role::Dialog));
+        // Not mvwprintw, SSIDs may contain %
+        let _ = mvwaddstr(win, 0, 2, &format!(" {ssid} "));
+        wattroff(win, theme::style(Role::Dialog));
+        for (i, line) in lines.iter().enumerate() {
+            let _ = mvwaddstr(win, i as i32 + 1, (width - qr_width) / 2, line);
+        }
+        let _ = mvwprintw(win, height - 2, 2, "Scan to join, any key to close");
+        wrefresh(win);
+
+        wgetch(win);
+        // Wipe the code from the terminal, not just from memory
+        werase(win);
+        wrefresh(win);
+        delwin(win);
+    }
+}
@@ -0,0 +1,182 @@
+use ncurses::*;
+
+use super::{calculate_display_range, navigate, NetworkUi};
+use crate::form::{self, Form};
+use crate::keymap::Action;
+use crate::nmcli::{self, Connection};
+use crate::theme::{self, Role};
+use crate::wireguard::{self, WireGuardStats};
+
+/// A WireGuard profile with the statistics of its interface when active.
+pub(super) struct Tunnel {
+    connection: Connection,
+    stats: Option<WireGuardStats>,
+}
+
+impl NetworkUi {
+    /// Reloads the WireGuard profiles and their statistics.
+    pub(super) fn refresh_tunnels(&mut self) {
+        match nmcli::connections() {
+            Ok(connections) => {
+                self.tunnels = connections
+                    .into_iter()
+                    .filter(|c| c.kind == "wireguard")
+                    .map(|connection| Tunnel {
+                        connection,
+                        stats: None,
+                    })
+                    .collect();
+            }
+            Err(e) => {
+                self.tunnels.clear();
+                form::show_message("WireGuard", &e.to_string());
+            }
+        }
+        if self.tunnel_highlight >= self.tunnels.len() {
+            self.tunnel_highlight = self.tunnels.len().saturating_sub(1);
+        }
+        self.refresh_tunnel_stats();
+    }
+
+    /// Re-reads traffic counters and handshakes without reloading profiles.
+    pub(super) fn refresh_tunnel_stats(&mut self) {
+        for tunnel in &mut self.tunnels {
+            tunnel.stats = if tunnel.connection.active && !tunnel.connection.device.is_empty() {
+                Some(wireguard::stats(&tunnel.connection.device))
+            } else {
+                None
+            };
+        }
+    }
+
+    pub(super) fn display_tunnels(&self) {
+        let active = self.tunnels.iter().filter(|t| t.connection.active).count();
+        self.draw_frame(
+            "WireGuard",
+            &format!("{active} active"),
+            &self.settings.keymap.footer(&[
+                (Action::NextView, "View"),
+                (Action::Rescan, "Refresh"),
+                (Action::Connect, "Up/Down"),
+                (Action::Import, "Import .conf"),
+                (Action::IpSettings, "IP"),
+                (Action::Quit, "Quit"),
+            ]),
+        );
+
+        if self.tunnels.is_empty() {
+            wattron(self.ui.win(), theme::style(Role::Text));
+            let _ = mvwprintw(
+                self.ui.win(),
+                4,
+                3,
+                "No WireGuard profiles. Press a to import a wg-quick .conf file.",
+            );
+            wattroff(self.ui.win(), theme::style(Role::Text));
+            wrefresh(self.ui.win());
+            return;
+        }
+
+        let name_width = self
+            .tunnels
+            .iter()
+            .map(|t| t.connection.name.len())
+            .max()
+            .unwrap_or(0);
+        let win_height = std::cmp::max(0, getmaxy(self.ui.win()) - 4) as usize;
+        let (start_index, end_index) = calculate_display_range(
+            self.tunnel_highlight,
+            self.tunnels.len(),
+            win_height,
+            &self.tunnel_scroll,
+        );
+
+        let tunnels = self.tunnels.iter().enumerate();
+        for (i, tunnel) in tunnels.take(end_index).skip(start_index) {
+            let marker = if tunnel.connection.active { "> " } else { "  " };
+            let details = match tunnel.stats {
+                Some(stats) => {
+                    let handshake = match stats.handshake_age {
+                        Some(age) => format!("handshake {} ago", format_age(age)),
+                        None => String::from("handshake n/a"),
+                    };
+                    format!(
+                        "{}  rx {}  tx {}  {handshake}",
+                        tunnel.connection.device,
+                        wireguard::format_bytes(stats.rx_bytes),
+                        wireguard::format_bytes(stats.tx_bytes),
+                    )
+                }
+                None => String::from("inactive"),
+            };
+            let line = format!("{marker}{:<name_width$}  {details}", tunnel.connection.name);
+
+            let mut attr = if tunnel.connection.active {
+                theme::style(Role::Connected)
+            } else {
+                theme::style(Role::Text)
+            };
+            if i == self.tunnel_highlight {
+                attr = theme::selected(attr);
+            }
+            wattron(self.ui.win(), attr);
+            // Not mvwprintw, names come from imported files and may contain %
+            let _ = mvwaddstr(self.ui.win(), (i - start_index + 3) as i32, 1, &line);
+            wattroff(self.ui.win(), attr);
+        }
+
+        wrefresh(self.ui.win());
+    }
+
+    pub(super) fn handle_tunnels_action(&mut self, action: Action) {
+        let page = self.page_size();
+        let selected = self
+            .tunnels
+            .get(self.tunnel_highlight)
+            .map(|t| t.connection.clone());
+        match (action, selected) {
+            (Action::Rescan, _) => self.refresh_tunnels(),
+            (Action::Import, _) => self.import_tunnel(),
+            (_, _) if navigate(&mut self.tunnel_highlight, self.tunnels.len(), page, action) => {}
+            (Action::Connect, Some(connection)) => {
+                self.toggle_connection(&connection);
+                self.refresh_tunnels();
+            }
+            (Action::IpSettings, Some(connection)) => {
+                self.edit_ip_settings(&connection.uuid, &connection.name);
+            }
+            _ => {} // Ignore actions that do not apply
+        }
+    }
+
+    fn import_tunnel(&mut self) {
+        let mut form = Form::new("Import WireGuard config").text("File", "");
+        if !form.run(|form| {
+            if form.value("File").trim().is_empty() {
+                Err(String::from("Enter the path of a wg-quick .conf file"))
+            } else {
+                Ok(())
+            }
+        }) {
+            return;
+        }
+
+        let path = form.value("File").to_string();
+        let result = self.run_task(String::from("Importing WireGuard config..."), move || {
+            wireguard::import(&path)
+        });
+        match result {
+            Ok(name) => form::show_message("WireGuard", &format!("Imported profile {name}")),
+            Err(e) => form::show_message("WireGuard", &e.to_string()),
+        }
+        self.refresh_tunnels();
+    }
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::NetworkUi;
+use crate::form;
+use crate::state::{self, Event};
+use crate::wps::{self, PBC_TIMEOUT_SECS};
+
+impl NetworkUi {
+    /// Connects to the highlighted network with WPS push-button instead of
+    /// asking for a password, counting down while the router's button is pressed.
+    pub(super) fn wps_connect(&self, index: usize) {
+        let Some(network) = self.networks.get(index) else {
+            return;
+        };
+
+        match wps::supports_pbc(&network.dbus_path) {
+            Ok(true) => {}
+            Ok(false) => {
+                form::show_message(
+                    "WPS",
+                    &format!("{} does not advertise WPS push-button.", network.ssid),
+                );
+                return;
+            }
+            Err(e) => {
+                form::show_message("WPS", &e.to_string());
+                return;
+            }
+        }
+
+        let ssid = network.ssid.clone();
+        let device = self.current_device().map(str::to_string);
+        let (tx, rx) = mpsc::channel();
+        let operation = thread::spawn(move || {
+            let _ = tx.send(wps::connect_pbc(&ssid, device.as_deref()));
+        });
+
+        let mut ui = self.ui.clone();
+        let started = Instant::now();
+        let result = loop {
+            if let Ok(result) = rx.recv_timeout(Duration::from_millis(50)) {
+                break result;
+            }
+            let remaining = PBC_TIMEOUT_SECS.saturating_sub(started.elapsed().as_secs());
+            ui.loading_animation(&format!(
+                "Press the WPS button on the {} router... {remaining}s",
+                network.ssid
+            ));
+        };
+        let _ = operation.join();
+        state::record(Event::Connect, &network.ssid, &network.bssid, &result);
+
+        match result {
+            Ok(()) => form::show_message("WPS", &format!("Connected to {}.", network.ssid)),
+            Err(e) => form::show_message(
+                "WPS",
+                &format!("WPS connection to {} failed:\n{e}", network.ssid),
+            ),
+        }
+    }
+}
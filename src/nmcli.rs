@@ -0,0 +1,782 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use zeroize::Zeroizing;
+
+use crate::network::{Network, NetworkError};
+use crate::qr::EapSettings;
+
+/// Runs `nmcli` with the given arguments and returns its standard output.
+///
+/// A non-zero exit status is turned into `NetworkError::CommandFailed`
+/// carrying whatever nmcli printed on standard error.
+pub fn run(args: &[&str]) -> Result<String, NetworkError> {
+    let output = Command::new("nmcli")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| NetworkError::CommandFailed(e.to_string()))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(NetworkError::CommandFailed(stderr.trim().to_string()))
+    }
+}
+
+/// Reads a secret property such as `802-11-wireless-security.psk` of a profile.
+///
+/// The output buffer is zeroed on drop, like the password typed in the TUI.
+pub fn connection_secret(uuid: &str, property: &str) -> Result<Zeroizing<String>, NetworkError> {
+    let output = Command::new("nmcli")
+        .args(["-s", "-g", property, "connection", "show", "uuid", uuid])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| NetworkError::CommandFailed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(NetworkError::CommandFailed(format!(
+            "Cannot read the secret of {uuid}"
+        )));
+    }
+
+    let stdout = Zeroizing::new(output.stdout);
+    let mut secret = Zeroizing::new(String::from_utf8_lossy(&stdout).into_owned());
+    let len = secret.trim_end_matches('\n').len();
+    secret.truncate(len);
+    Ok(secret)
+}
+
+/// Undoes the `\:` and `\\` escaping nmcli applies to terse output values.
+/// Any other escaped character, as in Wi-Fi QR payloads, is kept as well.
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                result.push(next);
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Reads the requested properties of a connection profile.
+///
+/// Values come back in the order of `fields`; unset properties are empty.
+pub fn connection_fields(id: &str, fields: &[&str]) -> Result<Vec<String>, NetworkError> {
+    let joined = fields.join(",");
+    let output = run(&["-t", "-f", &joined, "connection", "show", id])?;
+
+    let mut values = vec![String::new(); fields.len()];
+    for line in output.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if let Some(pos) = fields.iter().position(|f| *f == name) {
+            let value = unescape(value);
+            values[pos] = if value == "--" { String::new() } else { value };
+        }
+    }
+    Ok(values)
+}
+
+/// Applies `property value` pairs to a connection profile in one call.
+pub fn modify_connection(id: &str, changes: &[(&str, &str)]) -> Result<(), NetworkError> {
+    let mut args = vec!["connection", "modify", id];
+    for (property, value) in changes {
+        args.push(property);
+        args.push(value);
+    }
+    run(&args).map(|_| ())
+}
+
+/// Per-profile options that can be edited from the TUI.
+#[derive(Debug, Clone)]
+pub struct ProfileSettings {
+    pub autoconnect: bool,
+    pub autoconnect_priority: i32,
+    /// One of `unknown`, `yes` or `no`.
+    pub metered: String,
+    /// `permanent`, `preserve`, `random`, `stable`, a MAC address, or empty
+    /// for the NetworkManager default.
+    pub cloned_mac_address: String,
+    /// BSSID the profile is locked to, empty when any access point may be used.
+    pub bssid: String,
+}
+
+const PROFILE_SETTINGS_FIELDS: [&str; 5] = [
+    "connection.autoconnect",
+    "connection.autoconnect-priority",
+    "connection.metered",
+    "802-11-wireless.cloned-mac-address",
+    "802-11-wireless.bssid",
+];
+
+impl ProfileSettings {
+    pub fn load(id: &str) -> Result<Self, NetworkError> {
+        let values = connection_fields(id, &PROFILE_SETTINGS_FIELDS)?;
+        Ok(ProfileSettings {
+            autoconnect: values[0] != "no",
+            autoconnect_priority: values[1].parse().unwrap_or(0),
+            metered: match values[2].as_str() {
+                // nmcli prints e.g. "yes (guessed)" for heuristically detected values
+                v if v.starts_with("yes") => String::from("yes"),
+                v if v.starts_with("no") => String::from("no"),
+                _ => String::from("unknown"),
+            },
+            cloned_mac_address: values[3].clone(),
+            bssid: values[4].clone(),
+        })
+    }
+
+    pub fn apply(&self, id: &str) -> Result<(), NetworkError> {
+        let priority = self.autoconnect_priority.to_string();
+        modify_connection(
+            id,
+            &[
+                (
+                    PROFILE_SETTINGS_FIELDS[0],
+                    if self.autoconnect { "yes" } else { "no" },
+                ),
+                (PROFILE_SETTINGS_FIELDS[1], &priority),
+                (PROFILE_SETTINGS_FIELDS[2], &self.metered),
+                (PROFILE_SETTINGS_FIELDS[3], &self.cloned_mac_address),
+                (PROFILE_SETTINGS_FIELDS[4], &self.bssid),
+            ],
+        )
+    }
+}
+
+/// Returns true when `value` looks like a colon separated MAC address.
+pub fn is_mac_address(value: &str) -> bool {
+    let parts: Vec<&str> = value.split(':').collect();
+    parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Splits a line of terse nmcli output into fields, honouring `\:` escapes.
+pub fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ':' => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+/// A saved Wi-Fi connection profile.
+#[derive(Debug, Clone)]
+pub struct WifiProfile {
+    pub name: String,
+    pub uuid: String,
+    pub ssid: String,
+    /// Empty for open networks, otherwise e.g. `wpa-psk`, `sae` or `wpa-eap`.
+    pub key_mgmt: String,
+}
+
+impl WifiProfile {
+    /// Whether NetworkManager can activate the profile without asking for a
+    /// secret. Only PSK based networks are checked; other methods are left
+    /// to NetworkManager's own secret agents.
+    ///
+    /// The secret must be kept by NetworkManager, neither agent-owned (1) nor
+    /// not-saved (2), and actually be set: profiles added without a key have
+    /// flags 0 too. The key read for that is dropped right away.
+    pub fn has_secret(&self) -> bool {
+        let (flags, secret) = match self.key_mgmt.as_str() {
+            "wpa-psk" | "sae" => (
+                "802-11-wireless-security.psk-flags",
+                "802-11-wireless-security.psk",
+            ),
+            "none" => (
+                "802-11-wireless-security.wep-key-flags",
+                "802-11-wireless-security.wep-key0,802-11-wireless-security.wep-key1,\
+                 802-11-wireless-security.wep-key2,802-11-wireless-security.wep-key3",
+            ),
+            _ => return true,
+        };
+        // Printed as e.g. "0 (none)" or "2 (not saved)"
+        let saved = run(&["-g", flags, "connection", "show", "uuid", &self.uuid])
+            .ok()
+            .and_then(|flags| flags.split_whitespace().next()?.parse::<u32>().ok())
+            .is_some_and(|flags| flags & 0b11 == 0);
+        // One line per property
+        saved
+            && connection_secret(&self.uuid, secret)
+                .is_ok_and(|secret| secret.lines().any(|line| !line.is_empty()))
+    }
+}
+
+/// What NetworkManager knows about a given SSID.
+#[derive(Debug, Clone)]
+pub enum ProfileStatus {
+    /// No profile has this SSID.
+    NoProfile,
+    /// A profile exists but activating it would need a secret.
+    SavedProfile(WifiProfile),
+    /// A profile exists and holds everything needed to connect.
+    SavedSecret(WifiProfile),
+}
+
+/// Lists every saved Wi-Fi profile together with its SSID and key management.
+pub fn wifi_profiles() -> Result<Vec<WifiProfile>, NetworkError> {
+    let output = run(&["-t", "-f", "UUID,TYPE", "connection", "show"])?;
+    let uuids: Vec<String> = output
+        .lines()
+        .map(split_terse)
+        .filter(|fields| fields.len() == 2 && fields[1] == "802-11-wireless")
+        .map(|mut fields| fields.swap_remove(0))
+        .collect();
+    if uuids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // `connection show` accepts several profiles at once and prints their
+    // properties back to back, each block starting with connection.id
+    let mut args = vec![
+        "-t",
+        "-f",
+        "connection.id,connection.uuid,802-11-wireless.ssid,802-11-wireless-security.key-mgmt",
+        "connection",
+        "show",
+    ];
+    for uuid in &uuids {
+        args.push("uuid");
+        args.push(uuid);
+    }
+    let output = run(&args)?;
+
+    let mut profiles: Vec<WifiProfile> = Vec::new();
+    for line in output.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = unescape(value);
+        let value = if value == "--" { String::new() } else { value };
+        if name == "connection.id" {
+            profiles.push(WifiProfile {
+                name: value,
+                uuid: String::new(),
+                ssid: String::new(),
+                key_mgmt: String::new(),
+            });
+            continue;
+        }
+        let Some(profile) = profiles.last_mut() else {
+            continue;
+        };
+        match name {
+            "connection.uuid" => profile.uuid = value,
+            "802-11-wireless.ssid" => profile.ssid = value,
+            "802-11-wireless-security.key-mgmt" => profile.key_mgmt = value,
+            _ => {}
+        }
+    }
+    Ok(profiles)
+}
+
+/// Returns the saved profiles for `ssid`, matching on the
+/// 802-11-wireless.ssid property rather than the connection name.
+pub fn find_profiles(ssid: &str) -> Vec<WifiProfile> {
+    if ssid.is_empty() {
+        return Vec::new();
+    }
+
+    wifi_profiles()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.ssid == ssid)
+        .collect()
+}
+
+/// SSIDs of every saved Wi-Fi profile.
+pub fn saved_ssids() -> HashSet<String> {
+    wifi_profiles()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.ssid)
+        .collect()
+}
+
+/// Tells apart "no profile", "saved profile" and "saved secret" for `ssid`.
+pub fn profile_status(ssid: &str) -> ProfileStatus {
+    // Prefer a profile that can connect straight away
+    let mut first = None;
+    for profile in find_profiles(ssid) {
+        if profile.has_secret() {
+            return ProfileStatus::SavedSecret(profile);
+        }
+        first.get_or_insert(profile);
+    }
+    first.map_or(ProfileStatus::NoProfile, ProfileStatus::SavedProfile)
+}
+
+/// Returns the UUIDs of all currently active connections.
+pub fn active_uuids() -> Vec<String> {
+    run(&["-t", "-f", "UUID", "connection", "show", "--active"])
+        .map(|output| output.lines().map(unescape).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the names of all currently active connections.
+pub fn active_names() -> Vec<String> {
+    run(&["-t", "-f", "NAME", "connection", "show", "--active"])
+        .map(|output| output.lines().map(unescape).collect())
+        .unwrap_or_default()
+}
+
+/// Lists the interface names of all Wi-Fi devices NetworkManager knows about.
+pub fn wifi_devices() -> Vec<String> {
+    run(&["-t", "-f", "DEVICE,TYPE", "device"])
+        .map(|output| {
+            output
+                .lines()
+                .map(split_terse)
+                .filter(|fields| fields.len() == 2 && fields[1] == "wifi")
+                .map(|mut fields| fields.swap_remove(0))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A saved connection profile of any type.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub name: String,
+    pub uuid: String,
+    /// NetworkManager connection type, e.g. `802-3-ethernet` or `vpn`.
+    pub kind: String,
+    /// VPN plugin name such as `openvpn`, empty for other types.
+    pub vpn_service: String,
+    /// Device the profile is active on, empty when inactive.
+    pub device: String,
+    pub active: bool,
+}
+
+impl Connection {
+    /// Human friendly name of the connection type.
+    pub fn type_label(&self) -> String {
+        match self.kind.as_str() {
+            "802-3-ethernet" => String::from("Ethernet"),
+            "802-11-wireless" => String::from("Wi-Fi"),
+            "vpn" if !self.vpn_service.is_empty() => format!("VPN ({})", self.vpn_service),
+            "vpn" => String::from("VPN"),
+            "wireguard" => String::from("WireGuard"),
+            "bridge" => String::from("Bridge"),
+            "bond" => String::from("Bond"),
+            "team" => String::from("Team"),
+            "vlan" => String::from("VLAN"),
+            "gsm" => String::from("Mobile (GSM)"),
+            "cdma" => String::from("Mobile (CDMA)"),
+            "bluetooth" => String::from("Bluetooth"),
+            "tun" => String::from("TUN/TAP"),
+            "loopback" => String::from("Loopback"),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Lists all saved connection profiles, active ones first.
+pub fn connections() -> Result<Vec<Connection>, NetworkError> {
+    let output = run(&[
+        "-t",
+        "-f",
+        "NAME,UUID,TYPE,DEVICE,ACTIVE",
+        "connection",
+        "show",
+    ])?;
+
+    let mut connections: Vec<Connection> = output
+        .lines()
+        .map(split_terse)
+        .filter(|fields| fields.len() == 5)
+        .map(|fields| Connection {
+            name: fields[0].clone(),
+            uuid: fields[1].clone(),
+            kind: fields[2].clone(),
+            vpn_service: String::new(),
+            device: if fields[3] == "--" {
+                String::new()
+            } else {
+                fields[3].clone()
+            },
+            active: fields[4] == "yes",
+        })
+        .collect();
+
+    // The plugin only shows up in the profile itself, e.g.
+    // org.freedesktop.NetworkManager.openvpn
+    for connection in connections.iter_mut().filter(|c| c.kind == "vpn") {
+        if let Ok(values) = connection_fields(&connection.uuid, &["vpn.service-type"]) {
+            connection.vpn_service = values[0].rsplit('.').next().unwrap_or("").to_string();
+        }
+    }
+
+    connections.sort_by_key(|c| !c.active);
+    Ok(connections)
+}
+
+/// Activates a connection profile, optionally on a specific interface.
+pub fn connection_up(uuid: &str, device: Option<&str>) -> Result<(), NetworkError> {
+    let mut args = vec!["connection", "up", "uuid", uuid];
+    if let Some(device) = device {
+        args.extend(["ifname", device]);
+    }
+    run(&args).map(|_| ())
+}
+
+/// Deactivates a connection profile.
+pub fn connection_down(uuid: &str) -> Result<(), NetworkError> {
+    run(&["connection", "down", "uuid", uuid]).map(|_| ())
+}
+
+/// Lists the access points seen by `device` (or every Wi-Fi device),
+/// strongest signal first.
+pub fn scan(device: Option<&str>) -> Result<Vec<Network>, NetworkError> {
+    let mut args = vec![
+        "-f",
+        // SIGNAL must stay last, it completes each record below
+        "IN-USE,SSID,BSSID,SECURITY,CHAN,FREQ,DBUS-PATH,SIGNAL",
+        "--mode",
+        "multiline",
+        "--terse",
+        "dev",
+        "wifi",
+        "list",
+    ];
+    if let Some(device) = device {
+        args.extend(["ifname", device]);
+    }
+    let output = run(&args)?;
+
+    let mut networks = Vec::new();
+    let mut network = Network::new();
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix("IN-USE:") {
+            network.in_use = value.trim() == "*";
+        } else if let Some(value) = line.strip_prefix("SSID:") {
+            network.ssid = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("BSSID:") {
+            network.bssid = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("SECURITY:") {
+            network.security = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("CHAN:") {
+            network.channel = value.trim().parse().unwrap_or(0);
+        } else if let Some(value) = line.strip_prefix("FREQ:") {
+            // "2437 MHz"
+            network.frequency = value
+                .split_whitespace()
+                .next()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+        } else if let Some(value) = line.strip_prefix("DBUS-PATH:") {
+            network.dbus_path = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("SIGNAL:") {
+            network.signal = value.trim().parse::<u8>().unwrap_or(0);
+            networks.push(std::mem::take(&mut network));
+        }
+    }
+
+    networks.sort_by_key(|n| std::cmp::Reverse(n.signal));
+    Ok(networks)
+}
+
+/// Connects to a network given what is already saved for it.
+///
+/// `target` is the BSSID or SSID used when no profile exists yet. A saved
+/// profile without a secret is activated with `password` handed over through
+/// `passwd-file`, so neither a duplicate profile nor the password on the
+/// command line is needed.
+pub fn activate(
+    status: &ProfileStatus,
+    target: &str,
+    password: &str,
+    hidden: bool,
+    device: Option<&str>,
+) -> Result<(), NetworkError> {
+    let profile = match status {
+        ProfileStatus::SavedSecret(profile) => profile,
+        ProfileStatus::SavedProfile(profile) => {
+            let property = if profile.key_mgmt == "none" {
+                "802-11-wireless-security.wep-key0"
+            } else {
+                "802-11-wireless-security.psk"
+            };
+            return connection_up_with_secrets(&profile.uuid, device, &[(property, password)]);
+        }
+        ProfileStatus::NoProfile => {
+            let mut args = vec!["dev", "wifi", "connect", target];
+            if !password.is_empty() {
+                args.extend(["password", password]);
+            }
+            if hidden {
+                args.extend(["hidden", "yes"]);
+            }
+            if let Some(device) = device {
+                args.extend(["ifname", device]);
+            }
+            return run(&args).map(|_| ());
+        }
+    };
+    connection_up(&profile.uuid, device)
+}
+
+/// Brings down the active profile for `ssid`, whatever it is called.
+pub fn disconnect(ssid: &str) -> Result<(), NetworkError> {
+    let active = active_uuids();
+    match find_profiles(ssid)
+        .into_iter()
+        .find(|p| active.contains(&p.uuid))
+    {
+        Some(profile) => connection_down(&profile.uuid),
+        None => run(&["connection", "down", "id", ssid]).map(|_| ()),
+    }
+}
+
+/// Deletes every profile saved for `ssid` and returns how many there were.
+pub fn forget(ssid: &str) -> Result<usize, NetworkError> {
+    let profiles = find_profiles(ssid);
+    if profiles.is_empty() {
+        return Ok(0);
+    }
+
+    let mut args = vec!["connection", "delete"];
+    for profile in &profiles {
+        args.extend(["uuid", profile.uuid.as_str()]);
+    }
+    run(&args)?;
+    Ok(profiles.len())
+}
+
+/// A network device as reported by `nmcli device show`.
+#[derive(Debug, Clone, Default)]
+pub struct Device {
+    pub name: String,
+    pub kind: String,
+    /// State name without the numeric code, e.g. `connected` or `unmanaged`.
+    pub state: String,
+    pub connection: String,
+    pub hwaddr: String,
+    pub driver: String,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+}
+
+impl Device {
+    pub fn is_connected(&self) -> bool {
+        self.state.starts_with("connected")
+    }
+}
+
+/// Lists every device NetworkManager sees, managed or not.
+pub fn devices() -> Result<Vec<Device>, NetworkError> {
+    let output = run(&[
+        "-t",
+        "-f",
+        "GENERAL.DEVICE,GENERAL.TYPE,GENERAL.STATE,GENERAL.CONNECTION,GENERAL.HWADDR,GENERAL.DRIVER,IP4.ADDRESS,IP6.ADDRESS",
+        "device",
+        "show",
+    ])?;
+
+    let mut devices: Vec<Device> = Vec::new();
+    for line in output.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = unescape(value);
+        let value = if value == "--" { String::new() } else { value };
+        if name == "GENERAL.DEVICE" {
+            devices.push(Device {
+                name: value,
+                ..Device::default()
+            });
+            continue;
+        }
+        let Some(device) = devices.last_mut() else {
+            continue;
+        };
+        // Multi-valued properties come as IP4.ADDRESS[1], IP4.ADDRESS[2], ...
+        match name.split('[').next().unwrap_or(name) {
+            "GENERAL.TYPE" => device.kind = value,
+            "GENERAL.STATE" => {
+                // "100 (connected)" -> "connected"
+                device.state = match (value.find('('), value.rfind(')')) {
+                    (Some(start), Some(end)) if start < end => value[start + 1..end].to_string(),
+                    _ => value,
+                };
+            }
+            "GENERAL.CONNECTION" => device.connection = value,
+            "GENERAL.HWADDR" => device.hwaddr = value,
+            "GENERAL.DRIVER" => device.driver = value,
+            "IP4.ADDRESS" if !value.is_empty() => device.ipv4.push(value),
+            "IP6.ADDRESS" if !value.is_empty() => device.ipv6.push(value),
+            _ => {}
+        }
+    }
+    Ok(devices)
+}
+
+/// Lets NetworkManager pick and activate a suitable profile on a device.
+pub fn device_connect(device: &str) -> Result<(), NetworkError> {
+    run(&["device", "connect", device]).map(|_| ())
+}
+
+/// Disconnects a device and stops it from auto-activating.
+pub fn device_disconnect(device: &str) -> Result<(), NetworkError> {
+    run(&["device", "disconnect", device]).map(|_| ())
+}
+
+/// Joins a WPA-Enterprise network, reusing the profile saved for `ssid` if
+/// there is one.
+///
+/// The password goes to nmcli through a private `passwd-file` rather than
+/// the command line, which every user can read in `/proc`. A profile created
+/// here is removed again when activating it fails, so retries do not pile
+/// up copies.
+pub fn activate_eap(
+    ssid: &str,
+    hidden: bool,
+    eap: &EapSettings,
+    password: &str,
+    device: Option<&str>,
+) -> Result<(), NetworkError> {
+    let settings = [
+        ("802-11-wireless-security.key-mgmt", "wpa-eap"),
+        ("802-1x.eap", eap.method.as_str()),
+        ("802-1x.identity", eap.identity.as_str()),
+        ("802-1x.anonymous-identity", eap.anonymous_identity.as_str()),
+        ("802-1x.phase2-auth", eap.phase2.as_str()),
+        ("802-11-wireless.hidden", if hidden { "yes" } else { "no" }),
+    ];
+
+    let (uuid, created) = match find_profiles(ssid).into_iter().next() {
+        Some(profile) => {
+            modify_connection(&profile.uuid, &settings)?;
+            (profile.uuid, false)
+        }
+        None => {
+            let mut args = vec![
+                "connection",
+                "add",
+                "type",
+                "wifi",
+                "con-name",
+                ssid,
+                "ssid",
+                ssid,
+            ];
+            for (property, value) in &settings {
+                args.extend([*property, *value]);
+            }
+            (add_connection(&args)?, true)
+        }
+    };
+
+    let result = connection_up_with_secrets(&uuid, device, &[("802-1x.password", password)]);
+    if result.is_err() && created {
+        let _ = run(&["connection", "delete", "uuid", &uuid]);
+    }
+    result
+}
+
+/// Like `connection_up`, answering NetworkManager's secret requests from a
+/// file only the user can read, deleted again afterwards.
+pub fn connection_up_with_secrets(
+    uuid: &str,
+    device: Option<&str>,
+    secrets: &[(&str, &str)],
+) -> Result<(), NetworkError> {
+    let error = |e: std::io::Error| NetworkError::CommandFailed(format!("passwd-file: {e}"));
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!("nmcurse-{}-{uuid}.secrets", std::process::id()));
+
+    let mut contents = Zeroizing::new(String::new());
+    for (property, value) in secrets {
+        contents.push_str(&format!("{property}:{value}\n"));
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(error)?;
+    let written = file.write_all(contents.as_bytes()).map_err(error);
+
+    let result = written.and_then(|()| {
+        let path = path.to_string_lossy();
+        let mut args = vec!["connection", "up", "uuid", uuid, "passwd-file", &path];
+        if let Some(device) = device {
+            args.extend(["ifname", device]);
+        }
+        run(&args).map(|_| ())
+    });
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Runs `nmcli connection add` with `args` and returns the new profile's UUID.
+pub fn add_connection(args: &[&str]) -> Result<String, NetworkError> {
+    // "Connection 'name' (<uuid>) successfully added."
+    let output = run(args)?;
+    output
+        .rsplit_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(uuid, _)| uuid.to_string())
+        .ok_or_else(|| NetworkError::CommandFailed(output.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescapes_colons_and_backslashes() {
+        assert_eq!(unescape("Home"), "Home");
+        assert_eq!(unescape(r"AA\:BB\:CC"), "AA:BB:CC");
+        assert_eq!(unescape(r"back\\slash"), r"back\slash");
+        assert_eq!(unescape(r"Café\:東京"), "Café:東京");
+    }
+
+    #[test]
+    fn drops_a_trailing_backslash() {
+        assert_eq!(unescape("end\\"), "end");
+    }
+
+    #[test]
+    fn splits_terse_fields() {
+        assert_eq!(split_terse("Home:100:WPA2"), ["Home", "100", "WPA2"]);
+        assert_eq!(split_terse("::"), ["", "", ""]);
+        assert_eq!(split_terse(""), [""]);
+    }
+
+    #[test]
+    fn keeps_escaped_colons_inside_a_field() {
+        assert_eq!(
+            split_terse(r"*:AA\:BB\:CC\:DD\:EE\:FF:Cafe\: bar"),
+            ["*", "AA:BB:CC:DD:EE:FF", "Cafe: bar"]
+        );
+        assert_eq!(split_terse(r"a\\:b"), [r"a\", "b"]);
+    }
+}
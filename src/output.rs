@@ -0,0 +1,263 @@
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::io::{self, Write};
+
+use crate::network::Network;
+use crate::nmcli::Device;
+use crate::radio::RadioState;
+
+/// Fields of a scanned network, in the order used when none are selected.
+///
+/// New fields are only ever appended so TSV columns stay stable.
+pub const NETWORK_FIELDS: [&str; 7] = [
+    "in_use",
+    "ssid",
+    "bssid",
+    "security",
+    "signal",
+    "channel",
+    "frequency",
+];
+
+/// Fields of a Wi-Fi device in `status`, in default order.
+pub const DEVICE_FIELDS: [&str; 6] = ["device", "state", "connection", "hwaddr", "ipv4", "ipv6"];
+
+// Plain output stays readable on a terminal by default
+const NETWORK_PLAIN_FIELDS: [&str; 5] = ["in_use", "ssid", "bssid", "signal", "security"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns with a header, for people.
+    Plain,
+    /// One JSON document on standard output.
+    Json,
+    /// Tab separated values without a header.
+    Tsv,
+}
+
+/// Why a result could not be printed.
+#[derive(Debug)]
+pub enum OutputError {
+    /// `--fields` named a field the records do not have.
+    Fields(String),
+    /// Standard output could not be written, e.g. a closed pipe.
+    Write(io::Error),
+}
+
+impl From<io::Error> for OutputError {
+    fn from(e: io::Error) -> Self {
+        OutputError::Write(e)
+    }
+}
+
+/// How the non-interactive commands print their results.
+#[derive(Debug, Clone, clap::Args)]
+pub struct Output {
+    /// Output format
+    #[arg(short = 'o', long = "output", value_enum, default_value_t = Format::Plain)]
+    pub format: Format,
+    /// Comma separated fields to print, or 'all'
+    #[arg(short = 'f', long, value_name = "LIST")]
+    pub fields: Option<String>,
+}
+
+impl Output {
+    // Resolves --fields against the fields a record type has; "all" picks
+    // every field
+    fn select(
+        &self,
+        available: &[&'static str],
+        default: &[&'static str],
+    ) -> Result<Vec<&'static str>, String> {
+        let Some(list) = &self.fields else {
+            return Ok(default.to_vec());
+        };
+        if list == "all" {
+            return Ok(available.to_vec());
+        }
+
+        list.split(',')
+            .map(|name| {
+                let name = name.trim();
+                available
+                    .iter()
+                    .find(|f| **f == name)
+                    .copied()
+                    .ok_or_else(|| {
+                        format!(
+                            "unknown field '{name}', expected one of: {}",
+                            available.join(",")
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Checks `--fields` before any slow work is done.
+    pub fn validate(&self, available: &[&'static str]) -> Result<(), String> {
+        self.select(available, available).map(|_| ())
+    }
+
+    pub fn print_networks(&self, networks: &[Network]) -> Result<(), OutputError> {
+        let default: &[&str] = if self.format == Format::Plain {
+            &NETWORK_PLAIN_FIELDS
+        } else {
+            &NETWORK_FIELDS
+        };
+        let fields = self
+            .select(&NETWORK_FIELDS, default)
+            .map_err(OutputError::Fields)?;
+        let rows: Vec<Vec<Value>> = networks
+            .iter()
+            .map(|n| fields.iter().map(|f| network_field(n, f)).collect())
+            .collect();
+
+        let mut out = io::stdout().lock();
+        match self.format {
+            Format::Json => print_json(&mut out, &Value::Array(records(&fields, rows)))?,
+            Format::Tsv => print_tsv(&mut out, &rows)?,
+            Format::Plain => print_plain(&mut out, &fields, &rows)?,
+        }
+        Ok(out.flush()?)
+    }
+
+    pub fn print_status(&self, radio: &RadioState, devices: &[Device]) -> Result<(), OutputError> {
+        let fields = self
+            .select(&DEVICE_FIELDS, &DEVICE_FIELDS)
+            .map_err(OutputError::Fields)?;
+        let rows: Vec<Vec<Value>> = devices
+            .iter()
+            .map(|d| fields.iter().map(|f| device_field(d, f)).collect())
+            .collect();
+
+        let mut out = io::stdout().lock();
+        match self.format {
+            Format::Json => print_json(
+                &mut out,
+                &json!({
+                "radio": {
+                    "enabled": radio.enabled,
+                    "soft_blocked": radio.soft_blocked,
+                    "hard_blocked": radio.hard_blocked,
+                },
+                "devices": records(&fields, rows),
+                }),
+            )?,
+            // Radio state has no row of its own, scripts wanting it use JSON
+            Format::Tsv => print_tsv(&mut out, &rows)?,
+            Format::Plain => {
+                writeln!(out, "{}", radio.describe())?;
+                writeln!(out)?;
+                print_plain(&mut out, &fields, &rows)?;
+            }
+        }
+        Ok(out.flush()?)
+    }
+}
+
+fn network_field(network: &Network, field: &str) -> Value {
+    match field {
+        "in_use" => json!(network.in_use),
+        "ssid" => json!(network.ssid),
+        "bssid" => json!(network.bssid),
+        "security" => json!(network.security),
+        "signal" => json!(network.signal),
+        "channel" => json!(network.channel),
+        "frequency" => json!(network.frequency),
+        _ => Value::Null,
+    }
+}
+
+fn device_field(device: &Device, field: &str) -> Value {
+    match field {
+        "device" => json!(device.name),
+        "state" => json!(device.state),
+        "connection" => json!(device.connection),
+        "hwaddr" => json!(device.hwaddr),
+        "ipv4" => json!(device.ipv4),
+        "ipv6" => json!(device.ipv6),
+        _ => Value::Null,
+    }
+}
+
+fn records(fields: &[&str], rows: Vec<Vec<Value>>) -> Vec<Value> {
+    rows.into_iter()
+        .map(|row| {
+            let record: Map<String, Value> =
+                fields.iter().map(|f| f.to_string()).zip(row).collect();
+            Value::Object(record)
+        })
+        .collect()
+}
+
+fn print_json(out: &mut impl Write, value: &Value) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    )
+}
+
+// Booleans read "yes"/"no" and lists are comma separated, as in nmcli's
+// terse mode
+fn text(value: &Value) -> String {
+    match value {
+        Value::Bool(b) => String::from(if *b { "yes" } else { "no" }),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(text).collect::<Vec<_>>().join(","),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// Backslash escapes keep every record on one line with a fixed column count
+fn escape_tsv(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn print_tsv(out: &mut impl Write, rows: &[Vec<Value>]) -> io::Result<()> {
+    for row in rows {
+        let line: Vec<String> = row.iter().map(|v| escape_tsv(&text(v))).collect();
+        writeln!(out, "{}", line.join("\t"))?;
+    }
+    Ok(())
+}
+
+fn print_plain(out: &mut impl Write, fields: &[&str], rows: &[Vec<Value>]) -> io::Result<()> {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|v| match text(v) {
+                    s if s.is_empty() => String::from("--"),
+                    s => s,
+                })
+                .collect()
+        })
+        .collect();
+    let header: Vec<String> = fields.iter().map(|f| f.to_uppercase()).collect();
+
+    let widths: Vec<usize> = (0..fields.len())
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for row in std::iter::once(&header).chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
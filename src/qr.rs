@@ -0,0 +1,311 @@
+use qrcode::{Color, EcLevel, QrCode};
+use zeroize::Zeroizing;
+
+use crate::nmcli::unescape;
+
+// Light modules around the symbol; the spec asks for 4 but 2 scans fine on screens
+const QUIET_ZONE: usize = 2;
+
+/// Builds the `WIFI:T:WPA;S:<ssid>;P:<psk>;;` payload understood by phone cameras.
+///
+/// `security` is `WPA`, `WEP` or `nopass`.
+pub fn wifi_payload(ssid: &str, security: &str, password: &str, hidden: bool) -> Zeroizing<String> {
+    let mut payload = Zeroizing::new(String::from("WIFI:"));
+    payload.push_str("T:");
+    payload.push_str(security);
+    payload.push_str(";S:");
+    push_escaped(&mut payload, ssid);
+    payload.push(';');
+    if security != "nopass" {
+        payload.push_str("P:");
+        push_escaped(&mut payload, password);
+        payload.push(';');
+    }
+    if hidden {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    payload
+}
+
+// Special characters of the WIFI: format are escaped with a backslash
+fn push_escaped(out: &mut String, value: &str) {
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Renders `data` as a QR code using Unicode half blocks, two modules per
+/// character cell.
+///
+/// Light modules are drawn as blocks so the code reads dark-on-light on the
+/// usual light-on-dark terminal, like `qrencode -t UTF8` does.
+pub fn render(data: &str) -> Result<Zeroizing<Vec<String>>, String> {
+    let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| format!("Cannot encode QR code: {e}"))?;
+    let width = code.width();
+    let colors = code.to_colors();
+    let size = width + 2 * QUIET_ZONE;
+
+    let is_light = |x: usize, y: usize| {
+        if x < QUIET_ZONE || y < QUIET_ZONE || x >= width + QUIET_ZONE || y >= width + QUIET_ZONE {
+            return true;
+        }
+        colors[(y - QUIET_ZONE) * width + (x - QUIET_ZONE)] == Color::Light
+    };
+
+    let mut lines = Zeroizing::new(Vec::with_capacity(size.div_ceil(2)));
+    for y in (0..size).step_by(2) {
+        let line: String = (0..size)
+            .map(
+                |x| match (is_light(x, y), y + 1 < size && is_light(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                },
+            )
+            .collect();
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// 802.1X settings carried by the EAP extension of the Wi-Fi QR format.
+#[derive(Debug, Clone, Default)]
+pub struct EapSettings {
+    /// Outer method, e.g. `peap` or `ttls`.
+    pub method: String,
+    pub anonymous_identity: String,
+    pub identity: String,
+    /// Inner authentication, e.g. `mschapv2`.
+    pub phase2: String,
+}
+
+/// The network described by a `WIFI:` QR payload.
+#[derive(Debug, Clone)]
+pub struct WifiCredentials {
+    pub ssid: String,
+    pub password: Zeroizing<String>,
+    pub hidden: bool,
+    pub eap: Option<EapSettings>,
+}
+
+/// Parses a `WIFI:T:WPA;S:<ssid>;P:<psk>;H:true;;` payload.
+///
+/// Fields may come in any order, `\` escapes `\ ; , : "`, and the EAP
+/// extension fields `E`, `A`, `I` and `PH2` are understood.
+pub fn parse_wifi_payload(payload: &str) -> Result<WifiCredentials, String> {
+    let payload = payload.trim();
+    let body = payload
+        .get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("WIFI:"))
+        .map(|_| &payload[5..])
+        .ok_or_else(|| String::from("Not a Wi-Fi QR payload (expected WIFI:...)"))?;
+
+    let mut ssid = None;
+    let mut security = String::new();
+    let mut password = Zeroizing::new(String::new());
+    let mut hidden = false;
+    let mut eap = EapSettings::default();
+
+    for field in split_fields(body).iter() {
+        let Some((key, raw)) = field.split_once(':') else {
+            if field.is_empty() {
+                continue;
+            }
+            return Err(format!("Malformed field {field:?}"));
+        };
+        let value = Zeroizing::new(unescape(raw));
+        match key.to_ascii_uppercase().as_str() {
+            "S" => ssid = Some(strip_quotes(&value).to_string()),
+            "T" => security = value.to_ascii_uppercase(),
+            "P" => password = Zeroizing::new(strip_quotes(&value).to_string()),
+            "H" => hidden = value.eq_ignore_ascii_case("true") || *value == "1",
+            "E" => eap.method = value.to_ascii_lowercase(),
+            "A" => eap.anonymous_identity = value.to_string(),
+            "I" => eap.identity = value.to_string(),
+            "PH2" => {
+                let phase2 = value.to_ascii_lowercase();
+                eap.phase2 = phase2.trim_start_matches("auth=").to_string();
+            }
+            _ => {} // Unknown fields are reserved for future use
+        }
+    }
+
+    let ssid = ssid
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| String::from("The payload has no SSID (S:)"))?;
+    if security.is_empty() {
+        security = if password.is_empty() {
+            String::from("nopass")
+        } else {
+            String::from("WPA")
+        };
+    }
+    if security == "NOPASS" {
+        security = String::from("nopass");
+    }
+
+    let is_eap = security.contains("EAP") || !eap.method.is_empty();
+    if !is_eap
+        && !matches!(
+            security.as_str(),
+            "WPA" | "WPA2" | "WPA3" | "SAE" | "WEP" | "nopass"
+        )
+    {
+        return Err(format!("Unsupported security type {security}"));
+    }
+    if !is_eap && security != "nopass" && password.is_empty() {
+        return Err(format!("{security} network {ssid} has no password (P:)"));
+    }
+    let eap = is_eap.then(|| {
+        if eap.method.is_empty() {
+            eap.method = String::from("peap");
+        }
+        eap
+    });
+
+    Ok(WifiCredentials {
+        ssid,
+        password,
+        hidden,
+        eap,
+    })
+}
+
+// Splits on unescaped semicolons, leaving escapes in place for unescape().
+// The fields hold the password, so they are wiped when dropped.
+fn split_fields(body: &str) -> Zeroizing<Vec<String>> {
+    let mut fields = Zeroizing::new(Vec::new());
+    // Never grows, so no copy of the password is left behind by a reallocation
+    let mut current = Zeroizing::new(String::with_capacity(body.len()));
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ';' => {
+                fields.push(current.to_string());
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        fields.push(current.to_string());
+    }
+    fields
+}
+
+// SSIDs and passwords that look like hex are wrapped in double quotes
+fn strip_quotes(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_special_characters() {
+        let ssid = r#"My;Net:work\ "1","#;
+        let password = r#"p;a:s\s,"w"#;
+        let payload = wifi_payload(ssid, "WPA", password, false);
+        assert_eq!(
+            *payload,
+            r#"WIFI:T:WPA;S:My\;Net\:work\\ \"1\"\,;P:p\;a\:s\\s\,\"w;;"#
+        );
+
+        let credentials = parse_wifi_payload(&payload).unwrap();
+        assert_eq!(credentials.ssid, ssid);
+        assert_eq!(*credentials.password, password);
+        assert!(!credentials.hidden);
+        assert!(credentials.eap.is_none());
+    }
+
+    #[test]
+    fn round_trips_hidden_open_networks() {
+        let payload = wifi_payload("Lobby", "nopass", "ignored", true);
+        assert_eq!(*payload, "WIFI:T:nopass;S:Lobby;H:true;;");
+
+        let credentials = parse_wifi_payload(&payload).unwrap();
+        assert_eq!(credentials.ssid, "Lobby");
+        assert!(credentials.password.is_empty());
+        assert!(credentials.hidden);
+    }
+
+    #[test]
+    fn accepts_any_field_order_and_case() {
+        let credentials = parse_wifi_payload("wifi:p:secret;h:1;s:Home;t:wpa;;").unwrap();
+        assert_eq!(credentials.ssid, "Home");
+        assert_eq!(*credentials.password, "secret");
+        assert!(credentials.hidden);
+    }
+
+    #[test]
+    fn strips_quotes_around_hex_looking_values() {
+        let credentials = parse_wifi_payload(r#"WIFI:S:"abcd";P:"1234abcd";;"#).unwrap();
+        assert_eq!(credentials.ssid, "abcd");
+        assert_eq!(*credentials.password, "1234abcd");
+    }
+
+    #[test]
+    fn reads_eap_fields() {
+        let payload = concat!(
+            "WIFI:T:WPA2-EAP;S:Campus;E:PEAP;A:anon@example.org;",
+            r"I:alice;P:pa\;ss;PH2:auth=MSCHAPV2;;",
+        );
+        let credentials = parse_wifi_payload(payload).unwrap();
+        assert_eq!(*credentials.password, "pa;ss");
+        let eap = credentials.eap.unwrap();
+        assert_eq!(eap.method, "peap");
+        assert_eq!(eap.anonymous_identity, "anon@example.org");
+        assert_eq!(eap.identity, "alice");
+        assert_eq!(eap.phase2, "mschapv2");
+    }
+
+    #[test]
+    fn defaults_the_eap_method_to_peap() {
+        let credentials = parse_wifi_payload("WIFI:T:WPA2-EAP;S:Campus;I:alice;;").unwrap();
+        assert_eq!(credentials.eap.unwrap().method, "peap");
+    }
+
+    #[test]
+    fn rejects_incomplete_payloads() {
+        let error = |payload| parse_wifi_payload(payload).unwrap_err();
+        assert_eq!(
+            error("T:WPA;S:Home;;"),
+            "Not a Wi-Fi QR payload (expected WIFI:...)"
+        );
+        assert_eq!(error("WIFI:T:WPA;P:x;;"), "The payload has no SSID (S:)");
+        assert_eq!(error("WIFI:S:;;"), "The payload has no SSID (S:)");
+        assert_eq!(
+            error("WIFI:T:WPA;S:Home;;"),
+            "WPA network Home has no password (P:)"
+        );
+        assert_eq!(
+            error("WIFI:T:TKIP;S:Home;P:x;;"),
+            "Unsupported security type TKIP"
+        );
+        assert_eq!(error("WIFI:S:Home;junk;;"), "Malformed field \"junk\"");
+    }
+
+    #[test]
+    fn keeps_an_escaped_semicolon_inside_a_field() {
+        assert_eq!(*split_fields(r"S:a\;b;P:c"), [r"S:a\;b", "P:c"]);
+        // A trailing backslash escapes nothing
+        let credentials = parse_wifi_payload("WIFI:S:Home\\").unwrap();
+        assert_eq!(credentials.ssid, "Home");
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::Path;
+
+use crate::network::NetworkError;
+use crate::nmcli;
+
+const RFKILL_PATH: &str = "/sys/class/rfkill";
+
+/// Software and hardware state of the Wi-Fi radio.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RadioState {
+    /// NetworkManager's WIRELESS_ENABLED switch.
+    pub enabled: bool,
+    /// Some wlan rfkill switch is soft blocked (e.g. airplane mode).
+    pub soft_blocked: bool,
+    /// Some wlan rfkill switch is hard blocked by a hardware key or BIOS.
+    pub hard_blocked: bool,
+}
+
+impl RadioState {
+    pub fn read() -> Self {
+        let enabled = nmcli::run(&["radio", "wifi"])
+            .map(|output| output.trim() == "enabled")
+            .unwrap_or(false);
+        let (soft_blocked, hard_blocked) = read_rfkill();
+        RadioState {
+            enabled,
+            soft_blocked,
+            hard_blocked,
+        }
+    }
+
+    pub fn is_usable(&self) -> bool {
+        self.enabled && !self.soft_blocked && !self.hard_blocked
+    }
+
+    /// Short text for the header, e.g. `Radio: off (soft blocked)`.
+    pub fn describe(&self) -> String {
+        let state = if self.is_usable() { "on" } else { "off" };
+        let block = match (self.soft_blocked, self.hard_blocked) {
+            (_, true) => " (hard blocked)",
+            (true, false) => " (soft blocked)",
+            _ => "",
+        };
+        format!("Radio: {state}{block}")
+    }
+}
+
+/// Switches the Wi-Fi radio on or off through NetworkManager.
+pub fn set_wifi_enabled(enabled: bool) -> Result<(), NetworkError> {
+    nmcli::run(&["radio", "wifi", if enabled { "on" } else { "off" }]).map(|_| ())
+}
+
+// Returns whether any wlan rfkill switch is soft or hard blocked
+fn read_rfkill() -> (bool, bool) {
+    let Ok(entries) = fs::read_dir(RFKILL_PATH) else {
+        return (false, false);
+    };
+
+    let mut soft_blocked = false;
+    let mut hard_blocked = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if read_attribute(&path, "type") != "wlan" {
+            continue;
+        }
+        soft_blocked |= read_attribute(&path, "soft") == "1";
+        hard_blocked |= read_attribute(&path, "hard") == "1";
+    }
+    (soft_blocked, hard_blocked)
+}
+
+fn read_attribute(path: &Path, name: &str) -> String {
+    fs::read_to_string(path.join(name))
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, DirBuilder, File, OpenOptions, Permissions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `$XDG_STATE_HOME/nmcurse`, falling back to `~/.local/state`.
+pub fn dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+        })?;
+    Some(base.join("nmcurse"))
+}
+
+/// History lines kept when the file outgrows `MAX_HISTORY_BYTES`.
+const MAX_HISTORY_ENTRIES: usize = 500;
+const MAX_HISTORY_BYTES: u64 = 128 * 1024;
+
+// The state tells where the user has been, so only they may read it
+fn create_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) => DirBuilder::new().recursive(true).mode(0o700).create(parent),
+        None => Ok(()),
+    }
+}
+
+fn open_private(path: &Path, options: &mut OpenOptions) -> std::io::Result<File> {
+    create_parent(path)?;
+    let file = options.mode(0o600).open(path)?;
+    // Files written before they were kept private
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+// Writes next to the file and renames, so a crash never leaves half a file
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("{}: {e}", path.display());
+    let temporary = path.with_extension("tmp");
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    let mut file = open_private(&temporary, &mut options).map_err(error)?;
+    file.write_all(contents.as_bytes()).map_err(error)?;
+    fs::rename(&temporary, path).map_err(error)
+}
+
+/// SSIDs pinned to the top of the Wi-Fi list, kept in `favorites.json`.
+#[derive(Debug, Clone, Default)]
+pub struct Favorites {
+    ssids: Vec<String>,
+}
+
+impl Favorites {
+    fn path() -> Option<PathBuf> {
+        dir().map(|dir| dir.join("favorites.json"))
+    }
+
+    /// Reads the favorites saved by earlier runs; none when the file does not
+    /// exist yet.
+    pub fn load() -> Result<Favorites, String> {
+        let Some(path) = Favorites::path() else {
+            return Ok(Favorites::default());
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Favorites::default()),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        };
+        let ssids =
+            serde_json::from_str(&contents).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Favorites { ssids })
+    }
+
+    pub fn contains(&self, ssid: &str) -> bool {
+        self.ssids.iter().any(|s| s == ssid)
+    }
+
+    /// Adds or removes `ssid` and saves the list. Returns whether it is a
+    /// favorite now. The list is left as it was when it cannot be saved.
+    pub fn toggle(&mut self, ssid: &str) -> Result<bool, String> {
+        let added = !self.contains(ssid);
+        let mut ssids = self.ssids.clone();
+        if added {
+            ssids.push(ssid.to_string());
+        } else {
+            ssids.retain(|s| s != ssid);
+        }
+        let path = Favorites::path().ok_or("no state directory, set XDG_STATE_HOME or HOME")?;
+        let contents = serde_json::to_string_pretty(&ssids).unwrap_or_default();
+        write(&path, &(contents + "\n"))?;
+        self.ssids = ssids;
+        Ok(added)
+    }
+}
+
+/// What a history entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+    Connect,
+    Disconnect,
+    Forget,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Connect => "connect",
+            Event::Disconnect => "disconnect",
+            Event::Forget => "forget",
+        }
+    }
+}
+
+/// One line of `history.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub event: Event,
+    pub ssid: String,
+    /// Empty when the access point was not known, e.g. joining from a QR code.
+    #[serde(default)]
+    pub bssid: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Formats the time as `YYYY-MM-DD HH:MM` in UTC.
+    pub fn time(&self) -> String {
+        let days = (self.timestamp / 86_400) as i64;
+        let minutes = self.timestamp % 86_400 / 60;
+        // Civil date from days since 1970-01-01, after Howard Hinnant
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        format!(
+            "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+            minutes / 60,
+            minutes % 60
+        )
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("history.jsonl"))
+}
+
+/// Appends an entry to the history, trimming it to the newest
+/// `MAX_HISTORY_ENTRIES` once it gets large. Failing to write it never stops
+/// the operation it describes, so errors are dropped.
+pub fn record<E: std::fmt::Display>(event: Event, ssid: &str, bssid: &str, result: &Result<(), E>) {
+    let entry = HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        event,
+        ssid: ssid.to_string(),
+        bssid: bssid.to_string(),
+        ok: result.is_ok(),
+        error: result.as_ref().err().map(ToString::to_string),
+    };
+    let Some(path) = history_path() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let mut options = OpenOptions::new();
+    if let Ok(mut file) = open_private(&path, options.create(true).append(true)) {
+        let _ = writeln!(file, "{line}");
+    }
+
+    // Keeps the newest entries once the file grows past the limit
+    let too_big = fs::metadata(&path).is_ok_and(|m| m.len() > MAX_HISTORY_BYTES);
+    if let (true, Ok(contents)) = (too_big, fs::read_to_string(&path)) {
+        let lines: Vec<&str> = contents.lines().collect();
+        let kept = &lines[lines.len().saturating_sub(MAX_HISTORY_ENTRIES)..];
+        let _ = write(&path, &(kept.join("\n") + "\n"));
+    }
+}
+
+/// Reads the history, oldest first. Lines that do not parse are skipped.
+pub fn history() -> Result<Vec<HistoryEntry>, String> {
+    let Some(path) = history_path() else {
+        return Ok(Vec::new());
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(timestamp: u64) -> String {
+        HistoryEntry {
+            timestamp,
+            event: Event::Connect,
+            ssid: String::new(),
+            bssid: String::new(),
+            ok: true,
+            error: None,
+        }
+        .time()
+    }
+
+    #[test]
+    fn formats_the_epoch() {
+        assert_eq!(time(0), "1970-01-01 00:00");
+    }
+
+    #[test]
+    fn formats_leap_days_and_year_ends() {
+        assert_eq!(time(951_782_400), "2000-02-29 00:00");
+        assert_eq!(time(1_709_211_599), "2024-02-29 12:59");
+        assert_eq!(time(1_767_225_599), "2025-12-31 23:59");
+        // 2100 is not a leap year
+        assert_eq!(time(4_107_542_399), "2100-02-28 23:59");
+        assert_eq!(time(4_107_542_400), "2100-03-01 00:00");
+    }
+}
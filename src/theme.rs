@@ -0,0 +1,471 @@
+use ncurses::*;
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// Parts of the interface that get their own colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Header,
+    Border,
+    Footer,
+    /// List rows without a more specific role.
+    Text,
+    SignalStrong,
+    SignalMedium,
+    SignalWeak,
+    Selected,
+    Connected,
+    Error,
+    Dialog,
+    /// Characters matching the search.
+    Search,
+}
+
+impl Role {
+    pub const ALL: [Role; 12] = [
+        Role::Header,
+        Role::Border,
+        Role::Footer,
+        Role::Text,
+        Role::SignalStrong,
+        Role::SignalMedium,
+        Role::SignalWeak,
+        Role::Selected,
+        Role::Connected,
+        Role::Error,
+        Role::Dialog,
+        Role::Search,
+    ];
+
+    // Color pairs are numbered after the roles, pair 0 stays the default
+    fn pair(self) -> i16 {
+        Role::ALL.iter().position(|r| *r == self).unwrap_or(0) as i16 + 1
+    }
+}
+
+/// A colour as written in a theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// The terminal's own foreground or background.
+    Default,
+    /// One of the 256 indexed colours, 0-15 being the named ones.
+    Index(u8),
+    Rgb(u8, u8, u8),
+}
+
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// Direct colour terminals (e.g. TERM=xterm-direct) take 0xRRGGBB as the colour
+const DIRECT_COLORS: i32 = 1 << 24;
+
+impl Color {
+    fn parse(s: &str) -> Result<Color, String> {
+        let s = s.trim();
+        if s == "default" {
+            return Ok(Color::Default);
+        }
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("invalid colour '{s}', expected #rrggbb")),
+            };
+        }
+        let (bright, name) = match s.strip_prefix("bright-") {
+            Some(name) => (8, name),
+            None => (0, s),
+        };
+        if let Some(i) = COLOR_NAMES.iter().position(|n| *n == name) {
+            return Ok(Color::Index(i as u8 + bright));
+        }
+        if let Ok(index) = s.parse() {
+            return Ok(Color::Index(index));
+        }
+        Err(format!(
+            "unknown colour '{s}', use default, a name such as red or bright-red, 0-255 or #rrggbb"
+        ))
+    }
+
+    // Approximate RGB value, using xterm's palette for the indexed colours
+    fn rgb(self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        match self {
+            Color::Default => (0, 0, 0),
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Index(i @ 0..=15) => BASIC[i as usize],
+            Color::Index(i @ 16..=231) => {
+                let i = (i - 16) as usize;
+                (LEVELS[i / 36], LEVELS[i / 6 % 6], LEVELS[i % 6])
+            }
+            Color::Index(i) => {
+                let level = 8 + (i - 232) * 10;
+                (level, level, level)
+            }
+        }
+    }
+
+    /// The colour number to give ncurses on a terminal with `colors` colours,
+    /// falling back to the closest colour the terminal has.
+    fn resolve(self, colors: i32) -> i32 {
+        let nearest = |candidates: std::ops::Range<i32>| {
+            let (r, g, b) = self.rgb();
+            candidates
+                .min_by_key(|i| {
+                    let (r2, g2, b2) = Color::Index(*i as u8).rgb();
+                    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+                    d(r, r2) + d(g, g2) + d(b, b2)
+                })
+                .unwrap_or(-1)
+        };
+        match self {
+            Color::Default => -1,
+            Color::Index(i) if colors >= DIRECT_COLORS && i >= 8 => {
+                let (r, g, b) = self.rgb();
+                (r as i32) << 16 | (g as i32) << 8 | b as i32
+            }
+            Color::Index(i) if (i as i32) < colors => i as i32,
+            Color::Rgb(r, g, b) if colors >= DIRECT_COLORS => {
+                (r as i32) << 16 | (g as i32) << 8 | b as i32
+            }
+            // The basic 16 differ between terminals, so only match the cube
+            // and the grey ramp
+            _ if colors >= 256 => nearest(16..256),
+            _ => nearest(0..colors.clamp(1, 16)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+
+        impl Visitor<'_> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a colour name, 0-255 or #rrggbb")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Color, E> {
+                u8::try_from(value)
+                    .map(Color::Index)
+                    .map_err(|_| E::custom(format!("colour {value} is outside 0-255")))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Color, E> {
+                Color::parse(value).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ColorVisitor)
+    }
+}
+
+/// Colours and attributes of one role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub dim: bool,
+    pub reverse: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    const fn new(fg: Color, bg: Color) -> Style {
+        Style {
+            fg,
+            bg,
+            bold: false,
+            dim: false,
+            reverse: false,
+            underline: false,
+        }
+    }
+
+    const fn bold(mut self) -> Style {
+        self.bold = true;
+        self
+    }
+
+    const fn underline(mut self) -> Style {
+        self.underline = true;
+        self
+    }
+
+    const fn reverse(mut self) -> Style {
+        self.reverse = true;
+        self
+    }
+
+    fn attrs(&self) -> attr_t {
+        [
+            (self.bold, A_BOLD()),
+            (self.dim, A_DIM()),
+            (self.reverse, A_REVERSE()),
+            (self.underline, A_UNDERLINE()),
+        ]
+        .into_iter()
+        .filter(|(on, _)| *on)
+        .fold(0, |attrs, (_, attr)| attrs | attr)
+    }
+
+    fn has_colors(&self) -> bool {
+        self.fg != Color::Default || self.bg != Color::Default
+    }
+}
+
+/// Style of every role.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: [Style; Role::ALL.len()],
+}
+
+/// Names of the themes that need no config.
+pub const BUILT_IN: [&str; 3] = ["dark", "light", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::built_in("dark").unwrap_or_else(Theme::monochrome)
+    }
+}
+
+impl Theme {
+    pub fn built_in(name: &str) -> Option<Theme> {
+        let (black, red, green, yellow, blue, magenta, cyan, white) = (
+            Color::Index(COLOR_BLACK as u8),
+            Color::Index(COLOR_RED as u8),
+            Color::Index(COLOR_GREEN as u8),
+            Color::Index(COLOR_YELLOW as u8),
+            Color::Index(COLOR_BLUE as u8),
+            Color::Index(COLOR_MAGENTA as u8),
+            Color::Index(COLOR_CYAN as u8),
+            Color::Index(COLOR_WHITE as u8),
+        );
+        let term = Color::Default;
+        let mut theme = Theme::plain();
+        let mut set = |role: Role, fg: Color, bg: Color| {
+            let style = theme.style_mut(role);
+            style.fg = fg;
+            style.bg = bg;
+        };
+        match name {
+            // Bright colours on a black background
+            "dark" => {
+                set(Role::Header, magenta, black);
+                set(Role::Border, magenta, black);
+                set(Role::Footer, magenta, black);
+                set(Role::Text, cyan, black);
+                set(Role::SignalStrong, green, black);
+                set(Role::SignalMedium, yellow, black);
+                set(Role::SignalWeak, red, black);
+                set(Role::Connected, green, black);
+                set(Role::Error, red, black);
+                set(Role::Dialog, magenta, black);
+                set(Role::Search, white, black);
+            }
+            // Darker colours on the terminal's own background
+            "light" => {
+                set(Role::Header, blue, term);
+                set(Role::Border, blue, term);
+                set(Role::Footer, blue, term);
+                set(Role::Text, blue, term);
+                set(Role::SignalStrong, green, term);
+                set(Role::SignalMedium, magenta, term);
+                set(Role::SignalWeak, red, term);
+                set(Role::Connected, green, term);
+                set(Role::Error, red, term);
+                set(Role::Dialog, blue, term);
+            }
+            "monochrome" => return Some(Theme::monochrome()),
+            _ => return None,
+        }
+        Some(theme)
+    }
+
+    // The terminal's default colours only, with attributes where they matter
+    fn plain() -> Theme {
+        let plain = Style::new(Color::Default, Color::Default);
+        let mut theme = Theme {
+            styles: [plain; Role::ALL.len()],
+        };
+        *theme.style_mut(Role::Selected) = plain.reverse();
+        *theme.style_mut(Role::Connected) = plain.bold();
+        *theme.style_mut(Role::Error) = plain.bold();
+        *theme.style_mut(Role::Dialog) = plain.bold();
+        *theme.style_mut(Role::Search) = plain.bold().underline();
+        theme
+    }
+
+    // Signal levels differ in brightness instead of colour
+    fn monochrome() -> Theme {
+        let mut theme = Theme::plain();
+        theme.style_mut(Role::SignalStrong).bold = true;
+        theme.style_mut(Role::SignalWeak).dim = true;
+        theme
+    }
+
+    pub fn style_mut(&mut self, role: Role) -> &mut Style {
+        &mut self.styles[role.pair() as usize - 1]
+    }
+
+    fn style(&self, role: Role) -> &Style {
+        &self.styles[role.pair() as usize - 1]
+    }
+
+    /// Sets up a color pair for each role. Needs `start_color` first when
+    /// the terminal has colours; without them the monochrome theme is used.
+    pub fn apply(&self) {
+        let colors = has_colors();
+        let monochrome;
+        let theme = if colors {
+            self
+        } else {
+            monochrome = Theme::monochrome();
+            &monochrome
+        };
+
+        let mut attrs = [0; Role::ALL.len()];
+        for (i, role) in Role::ALL.into_iter().enumerate() {
+            let style = theme.style(role);
+            if colors {
+                init_extended_pair(
+                    role.pair() as i32,
+                    style.fg.resolve(COLORS()),
+                    style.bg.resolve(COLORS()),
+                );
+            }
+            attrs[i] = style.attrs();
+        }
+        // Compared as drawn: on a small palette distinct colours may resolve
+        // to the same one
+        let signal = [Role::SignalStrong, Role::SignalMedium, Role::SignalWeak].map(|role| {
+            let style = theme.style(role);
+            (style.fg.resolve(COLORS()), style.bg.resolve(COLORS()))
+        });
+        let _ = ATTRS.set(Attrs {
+            attrs,
+            colors,
+            selected_colors: colors && theme.style(Role::Selected).has_colors(),
+            signal_colors: colors
+                && signal[0] != signal[1]
+                && signal[1] != signal[2]
+                && signal[0] != signal[2],
+        });
+    }
+}
+
+struct Attrs {
+    attrs: [attr_t; Role::ALL.len()],
+    colors: bool,
+    selected_colors: bool,
+    signal_colors: bool,
+}
+
+// Set once when the interface starts, read by every view and dialog
+static ATTRS: std::sync::OnceLock<Attrs> = std::sync::OnceLock::new();
+
+/// Attributes to draw `role` with.
+pub fn style(role: Role) -> attr_t {
+    match ATTRS.get() {
+        Some(a) if !a.colors => a.attrs[role.pair() as usize - 1],
+        Some(a) => COLOR_PAIR(role.pair()) | a.attrs[role.pair() as usize - 1],
+        None => COLOR_PAIR(role.pair()),
+    }
+}
+
+/// Whether the signal levels can be told apart by colour alone.
+pub fn signal_in_color() -> bool {
+    ATTRS.get().is_some_and(|a| a.signal_colors)
+}
+
+/// Attributes of a highlighted row drawn with `attr` otherwise. A selected
+/// style without colours keeps the row's colours and only adds its attributes.
+pub fn selected(attr: attr_t) -> attr_t {
+    match ATTRS.get() {
+        Some(a) if a.selected_colors => style(Role::Selected) | (attr & A_BOLD()),
+        Some(a) => attr | a.attrs[Role::Selected.pair() as usize - 1],
+        None => attr | A_REVERSE(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_numbers_and_hex() {
+        assert_eq!(Color::parse("default"), Ok(Color::Default));
+        assert_eq!(Color::parse("red"), Ok(Color::Index(1)));
+        assert_eq!(Color::parse("bright-white"), Ok(Color::Index(15)));
+        assert_eq!(Color::parse(" 208 "), Ok(Color::Index(208)));
+        assert_eq!(Color::parse("#1a2B3c"), Ok(Color::Rgb(0x1a, 0x2b, 0x3c)));
+    }
+
+    #[test]
+    fn rejects_malformed_colours() {
+        for s in ["#12345", "#1234567", "#gg0000", "#ééé"] {
+            assert!(
+                Color::parse(s).unwrap_err().starts_with("invalid colour"),
+                "{s}"
+            );
+        }
+        for s in ["purple", "bright-", "256", "-1", ""] {
+            assert!(
+                Color::parse(s).unwrap_err().starts_with("unknown colour"),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_colours_the_terminal_has() {
+        assert_eq!(Color::Default.resolve(256), -1);
+        assert_eq!(Color::Index(3).resolve(8), 3);
+        assert_eq!(Color::Index(200).resolve(256), 200);
+    }
+
+    #[test]
+    fn falls_back_to_the_nearest_colour() {
+        assert_eq!(Color::Index(9).resolve(8), 1);
+        assert_eq!(Color::Index(196).resolve(16), 9);
+        assert_eq!(Color::Rgb(255, 0, 0).resolve(256), 196);
+        assert_eq!(Color::Rgb(128, 128, 128).resolve(256), 244);
+    }
+
+    #[test]
+    fn passes_rgb_to_direct_colour_terminals() {
+        assert_eq!(
+            Color::Rgb(0x12, 0x34, 0x56).resolve(DIRECT_COLORS),
+            0x123456
+        );
+        assert_eq!(Color::Index(9).resolve(DIRECT_COLORS), 0xff0000);
+        // The first eight stay palette entries so they follow the terminal's
+        assert_eq!(Color::Index(3).resolve(DIRECT_COLORS), 3);
+    }
+}
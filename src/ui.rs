@@ -1,6 +1,23 @@
 use std::iter::Cycle;
 
-use ncurses::{COLOR_PAIR, *};
+use ncurses::*;
+
+use crate::theme::{self, Role, Theme};
+
+extern "C" {
+    // From the C library, the same table ncurses measures characters with.
+    // wchar_t is a 32-bit integer on Linux.
+    fn wcwidth(c: i32) -> std::ffi::c_int;
+}
+
+/// Terminal cells `text` takes up: two for wide characters such as CJK,
+/// none for combining marks.
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        // Unprintable characters come back as -1; ncurses shows them as one
+        .map(|c| usize::try_from(unsafe { wcwidth(c as i32) }).unwrap_or(1))
+        .sum()
+}
 
 pub struct Ui {
     win: WINDOW,
@@ -10,25 +27,23 @@ pub struct Ui {
 unsafe impl Send for Ui {}
 
 impl Ui {
-    pub fn new() -> Ui {
+    pub fn new(theme: &Theme) -> Ui {
         let _ = setlocale(LcCategory::all, "");
         initscr();
-        start_color();
-
-        // Initialize color pairs for signal levels
-        init_pair(1, COLOR_RED, COLOR_BLACK);
-        init_pair(2, COLOR_YELLOW, COLOR_BLACK);
-        init_pair(3, COLOR_GREEN, COLOR_BLACK);
-        init_pair(4, COLOR_CYAN, COLOR_BLACK);
-        init_pair(5, COLOR_MAGENTA, COLOR_BLACK);
+        if has_colors() {
+            start_color();
+            use_default_colors();
+        }
+
+        theme.apply();
         curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
 
         noecho();
         nonl();
         raw();
 
-        use_default_colors();
         let win = newwin(LINES(), COLS(), 0, 0);
+        keypad(win, true);
         Ui {
             win,
             spinner: vec!["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
@@ -55,16 +70,17 @@ impl Ui {
         self.clear();
         refresh();
 
-        // Draw the message with the spinner
-        wattron(self.win, COLOR_PAIR(5).try_into().unwrap());
-        let _ = mvwprintw(
+        // Draw the message with the spinner, not with mvwprintw as it may
+        // name an SSID containing %
+        wattron(self.win, theme::style(Role::Header));
+        let _ = mvwaddstr(
             self.win,
             1,
             1,
             format!("{} {}", message, self.spinner.next().unwrap()).as_str(),
         );
         mvwhline(self.win, 2, 1, 0, getmaxx(self.win) - 2);
-        wattroff(self.win, COLOR_PAIR(5).try_into().unwrap());
+        wattroff(self.win, theme::style(Role::Header));
 
         // Refresh the window to show changes
         wrefresh(self.win);
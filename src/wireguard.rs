@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::network::NetworkError;
+use crate::nmcli;
+
+// Linux interface names are limited to IFNAMSIZ - 1 characters
+const MAX_INTERFACE_NAME: usize = 15;
+
+/// Traffic counters and handshake age of an active WireGuard interface.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WireGuardStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// Seconds since the most recent handshake with any peer, if known.
+    pub handshake_age: Option<u64>,
+}
+
+/// Imports a wg-quick style `.conf` file as a NetworkManager profile.
+///
+/// NetworkManager takes the interface and profile name from the file name,
+/// so `~/vpn/office.conf` becomes a profile called `office`. Returns that name.
+pub fn import(path: &str) -> Result<String, NetworkError> {
+    let path = expand_home(path.trim());
+    let name = validate_config(&path)?;
+
+    let path = path.to_string_lossy();
+    nmcli::run(&[
+        "connection",
+        "import",
+        "type",
+        "wireguard",
+        "file",
+        path.as_ref(),
+    ])?;
+    Ok(name)
+}
+
+// Catches the mistakes nmcli reports poorly before handing the file over
+fn validate_config(path: &Path) -> Result<String, NetworkError> {
+    let invalid = |message: String| NetworkError::CommandFailed(message);
+
+    if path.extension().and_then(|e| e.to_str()) != Some("conf") {
+        return Err(invalid(String::from("WireGuard configs must end in .conf")));
+    }
+    let name = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_string();
+    if name.is_empty()
+        || name.len() > MAX_INTERFACE_NAME
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '=' | '+' | '.' | '-'))
+    {
+        return Err(invalid(format!(
+            "{name}.conf: the file name becomes the interface name and must be 1-{MAX_INTERFACE_NAME} characters of [a-zA-Z0-9_=+.-]"
+        )));
+    }
+
+    let contents =
+        fs::read_to_string(path).map_err(|e| invalid(format!("{}: {e}", path.display())))?;
+    let has_section = |section: &str| {
+        contents
+            .lines()
+            .any(|l| l.trim().eq_ignore_ascii_case(section))
+    };
+    for section in ["[Interface]", "[Peer]"] {
+        if !has_section(section) {
+            return Err(invalid(format!(
+                "{}: no {section} section found",
+                path.display()
+            )));
+        }
+    }
+
+    Ok(name)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Reads live statistics for an active WireGuard interface.
+///
+/// Byte counters come from sysfs and are always available; the handshake
+/// age needs `wg` and usually root, and is left empty otherwise.
+pub fn stats(interface: &str) -> WireGuardStats {
+    let counter = |name: &str| {
+        fs::read_to_string(format!("/sys/class/net/{interface}/statistics/{name}"))
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+    };
+
+    WireGuardStats {
+        rx_bytes: counter("rx_bytes"),
+        tx_bytes: counter("tx_bytes"),
+        handshake_age: latest_handshake(interface),
+    }
+}
+
+fn latest_handshake(interface: &str) -> Option<u64> {
+    let output = Command::new("wg")
+        .args(["show", interface, "latest-handshakes"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+
+    // One "<peer public key>\t<unix time>" line per peer, 0 when never
+    let latest = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| l.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .max()
+        .filter(|t| *t > 0)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(now.saturating_sub(latest))
+}
+
+/// Formats a byte count the way `wg show` does, e.g. `1.21 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.2} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,113 @@
+use std::process::{Command, Stdio};
+
+use crate::network::NetworkError;
+use crate::nmcli;
+
+/// How long routers keep the WPS push-button window open.
+pub const PBC_TIMEOUT_SECS: u64 = 120;
+
+// NM_802_11_AP_FLAGS_* from NetworkManager's D-Bus API
+const AP_FLAGS_WPS: u32 = 0x2;
+const AP_FLAGS_WPS_PBC: u32 = 0x4;
+
+/// Returns whether the access point advertises WPS push-button support.
+///
+/// nmcli does not print the access point flags, so they are read from
+/// NetworkManager over D-Bus with busctl.
+pub fn supports_pbc(dbus_path: &str) -> Result<bool, NetworkError> {
+    if dbus_path.is_empty() {
+        return Ok(false);
+    }
+
+    let output = Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            dbus_path,
+            "org.freedesktop.NetworkManager.AccessPoint",
+            "Flags",
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| NetworkError::CommandFailed(format!("busctl: {e}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(NetworkError::CommandFailed(stderr.trim().to_string()));
+    }
+
+    // Printed as "u 7"
+    let flags = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0);
+    Ok(flags & AP_FLAGS_WPS != 0 && flags & AP_FLAGS_WPS_PBC != 0)
+}
+
+/// Sets up a profile that lets NetworkManager obtain the PSK through WPS
+/// push-button, then activates it, waiting for the button press.
+///
+/// A profile already saved for `ssid` is switched to push-button instead of
+/// adding a second one, and put back as it was when the exchange fails; a
+/// profile created here is removed again.
+pub fn connect_pbc(ssid: &str, device: Option<&str>) -> Result<(), NetworkError> {
+    const FIELDS: [&str; 2] = [
+        "802-11-wireless-security.key-mgmt",
+        "802-11-wireless-security.wps-method",
+    ];
+    let timeout = PBC_TIMEOUT_SECS.to_string();
+
+    if let Some(profile) = nmcli::find_profiles(ssid).into_iter().next() {
+        let previous = nmcli::connection_fields(&profile.uuid, &FIELDS)?;
+        nmcli::modify_connection(&profile.uuid, &[(FIELDS[0], "wpa-psk"), (FIELDS[1], "pbc")])?;
+
+        let mut args = vec![
+            "--wait",
+            &timeout,
+            "connection",
+            "up",
+            "uuid",
+            &profile.uuid,
+        ];
+        if let Some(device) = device {
+            args.extend(["ifname", device]);
+        }
+        let result = nmcli::run(&args);
+        if result.is_err() {
+            // The method reads back as e.g. "0x0 (default)", set by its names
+            let method = match previous[1].split_once('(') {
+                Some((_, names)) => names.trim_end_matches(')').replace(' ', ""),
+                None => previous[1].clone(),
+            };
+            let restore = [(FIELDS[0], previous[0].as_str()), (FIELDS[1], &method)];
+            let _ = nmcli::modify_connection(&profile.uuid, &restore);
+        }
+        return result.map(|_| ());
+    }
+
+    let mut args = vec![
+        "connection",
+        "add",
+        "type",
+        "wifi",
+        "con-name",
+        ssid,
+        "ssid",
+        ssid,
+        "wifi-sec.key-mgmt",
+        "wpa-psk",
+        "wifi-sec.wps-method",
+        "pbc",
+    ];
+    if let Some(device) = device {
+        args.extend(["ifname", device]);
+    }
+
+    let uuid = nmcli::add_connection(&args)?;
+
+    let result = nmcli::run(&["--wait", &timeout, "connection", "up", "uuid", &uuid]);
+    if result.is_err() {
+        let _ = nmcli::run(&["connection", "delete", "uuid", &uuid]);
+    }
+    result.map(|_| ())
+}